@@ -6,10 +6,54 @@
 #![no_std]
 use stylus_sdk::{
     alloy_primitives::{Address, U256},
+    call::{static_call, Call},
     msg, prelude::*,
     stylus_proc::*,
 };
 
+// Incoterms 2020 codes (0=EXW, 1=FCA, 2=FAS, 3=FOB, 4=CFR, 5=CIF, 6=CPT, 7=CIP, 8=DAP, 9=DPU, 10=DDP)
+const MAX_INCOTERM: u8 = 10;
+
+// Document type codes (0=commercial invoice, 1=certificate of origin, 2=phytosanitary certificate,
+// 3=bill of lading, 4=packing list, 5=insurance certificate, 6-31=other)
+const MAX_DOCUMENT_TYPE: u8 = 31;
+
+// Maximum number of trade IDs returned by a single paginated view
+const MAX_PAGE_SIZE: u32 = 100;
+
+// Maximum number of index entries a single status-filtered view scans
+const MAX_SCAN_SIZE: u32 = 500;
+
+// Maximum number of trades created or funded in a single batch call
+const MAX_BATCH_SIZE: usize = 50;
+
+// Maximum platform or arbitration fee (1000 basis points = 10%)
+const MAX_FEE_BASIS_POINTS: u32 = 1000;
+
+// Maximum number of volume fee tiers
+const MAX_FEE_TIERS: u32 = 10;
+
+// Maximum refund grace period after expiry (30 days)
+const MAX_REFUND_GRACE_PERIOD: u32 = 30 * 24 * 60 * 60;
+
+// EIP-712 domain for signed trade offers
+const EIP712_DOMAIN_TYPE: &[u8] =
+    b"EIP712Domain(string name,string version,uint256 chainId,address verifyingContract)";
+const EIP712_DOMAIN_NAME: &[u8] = b"Arbitrum Pulse Trade Escrow";
+const EIP712_DOMAIN_VERSION: &[u8] = b"1";
+
+// EIP-712 type of an exporter's signed trade offer
+const TRADE_OFFER_TYPE: &[u8] = b"TradeOffer(address exporter,address importer,uint256 price,bytes32 descriptionHash,uint256 expiryDuration,uint8 incoterm,uint32 requiredDocuments,uint256 nonce,uint256 deadline)";
+
+// ecrecover precompile address
+const ECRECOVER: Address = Address::with_last_byte(1);
+
+// Half the secp256k1 curve order; signatures with a higher s value are rejected as malleable
+const SECP256K1_HALF_ORDER: [u8; 32] = [
+    0x7f, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+    0x5d, 0x57, 0x6e, 0x73, 0x57, 0xa4, 0x50, 0x1d, 0xdf, 0xe9, 0x2f, 0x46, 0x68, 0x1b, 0x20, 0xa0,
+];
+
 // Trade escrow contract storage
 #[derive(StorageField)]
 struct TradeEscrowStorage {
@@ -28,6 +72,84 @@ struct TradeEscrowStorage {
     // Platform fee collector address
     #[selector(0x4)]
     fee_collector: StorageAddress,
+    // Maps trade ID to its pending (not yet approved) amendment
+    #[selector(0x5)]
+    pending_amendments: StorageMap<U256, Amendment>,
+    // Maps trade ID to its approved amendment history
+    #[selector(0x6)]
+    amendment_history: StorageMap<U256, StorageMap<U256, Amendment>>,
+    // Maps trade ID to approved amendment count
+    #[selector(0x7)]
+    amendment_counts: StorageMap<U256, U256>,
+    // Maps trade ID to its attached documents
+    #[selector(0x8)]
+    documents: StorageMap<U256, StorageMap<U256, TradeDocument>>,
+    // Maps trade ID to attached document count
+    #[selector(0x9)]
+    document_counts: StorageMap<U256, U256>,
+    // Maps trade ID to bitmask of document types attached so far
+    #[selector(0xa)]
+    attached_document_types: StorageMap<U256, u32>,
+    // Arbitration fee charged on disputed trades (in basis points)
+    #[selector(0xb)]
+    arbitration_fee_basis_points: StorageU256,
+    // Maps trade ID to its dispute settlement record
+    #[selector(0xc)]
+    settlements: StorageMap<U256, Settlement>,
+    // Maps address to settled funds available for withdrawal
+    #[selector(0xd)]
+    balances: StorageMap<Address, U256>,
+    // Maps exporter address to their trade IDs (by index)
+    #[selector(0xe)]
+    exporter_trades: StorageMap<Address, StorageMap<U256, U256>>,
+    // Maps exporter address to their trade count
+    #[selector(0xf)]
+    exporter_trade_counts: StorageMap<Address, U256>,
+    // Maps importer address to their trade IDs (by index)
+    #[selector(0x10)]
+    importer_trades: StorageMap<Address, StorageMap<U256, U256>>,
+    // Maps importer address to their trade count
+    #[selector(0x11)]
+    importer_trade_counts: StorageMap<Address, U256>,
+    // Maps party address to their trade history statistics
+    #[selector(0x12)]
+    party_stats: StorageMap<Address, PartyStats>,
+    // Seconds after expiry before refunds become claimable
+    #[selector(0x13)]
+    refund_grace_period: StorageU256,
+    // Maps trade ID to its pending expiry extension request
+    #[selector(0x14)]
+    pending_extensions: StorageMap<U256, ExtensionRequest>,
+    // Maps tier index to volume fee tier (ascending by min_amount)
+    #[selector(0x15)]
+    fee_tiers: StorageMap<U256, FeeTier>,
+    // Number of volume fee tiers
+    #[selector(0x16)]
+    fee_tier_count: StorageU256,
+    // Cargo insurance premium charged on insured trades (in basis points)
+    #[selector(0x18)]
+    insurance_premium_basis_points: StorageU256,
+    // Total capital and premiums held in the insurance pool
+    #[selector(0x19)]
+    insurance_pool_balance: StorageU256,
+    // Pool capital reserved as coverage for funded insured trades
+    #[selector(0x1a)]
+    insurance_reserved: StorageU256,
+    // Maps underwriter address to their share of the insurance pool
+    #[selector(0x1b)]
+    underwriter_shares: StorageMap<Address, U256>,
+    // Total insurance pool shares issued
+    #[selector(0x1c)]
+    total_underwriter_shares: StorageU256,
+    // Maps trade ID to its pending partial settlement offer
+    #[selector(0x1d)]
+    pending_partial_settlements: StorageMap<U256, PartialSettlementOffer>,
+    // Maps exporter address to signed-offer nonces already used or cancelled
+    #[selector(0x1e)]
+    used_offer_nonces: StorageMap<Address, StorageMap<U256, bool>>,
+    // Maps trade ID to a guarantor nomination awaiting the exporter's acceptance
+    #[selector(0x1f)]
+    pending_guarantors: StorageMap<U256, GuarantorNomination>,
 }
 
 // Trade struct to store each transaction
@@ -37,9 +159,13 @@ struct Trade {
     exporter: Address,
     // Importer's address
     importer: Address,
+    // Agreed price the importer must fund
+    price: U256,
     // Amount in escrow
     amount: U256,
-    // Status of the trade (0=created, 1=funded, 2=delivered, 3=completed, 4=refunded, 5=disputed)
+    // Platform fee snapshotted at creation, re-quoted if an amendment changes the price (in basis points)
+    fee_basis_points: U256,
+    // Status of the trade (0=created, 1=funded, 2=delivered, 3=completed, 4=refunded, 5=disputed, 6=cancelled, 7=partially settled)
     status: u8,
     // Trade description (e.g., "10 bags of Ethiopian coffee")
     description_hash: [u8; 32],
@@ -47,6 +173,140 @@ struct Trade {
     created_at: U256,
     // Trade expiration timestamp
     expires_at: U256,
+    // Seconds after expiry before a refund can be claimed, snapshotted at creation
+    refund_grace_period: U256,
+    // Timestamp when the trade was funded
+    funded_at: U256,
+    // Agreed Incoterm (e.g., 3=FOB, 5=CIF)
+    incoterm: u8,
+    // Bitmask of document types required before release (bit n = document type n, 0 = none)
+    required_documents: u32,
+    // Issuing bank/guarantor funding on the importer's behalf (zero if none)
+    guarantor: Address,
+    // Whether the guarantor has approved document compliance
+    guarantor_approved: bool,
+    // Whether the exporter requested cargo insurance
+    insurance_requested: bool,
+    // Whether cover is agreed (the funding party accepted the exporter's request)
+    insured: bool,
+    // Insurance premium paid into the pool at funding
+    insurance_premium: U256,
+}
+
+// Document attached to a trade by one of the parties
+#[derive(PartialEq, Clone)]
+struct TradeDocument {
+    // Document type code (e.g., 2=phytosanitary certificate)
+    doc_type: u8,
+    // Hash of the document (e.g., IPFS hash to the scanned certificate)
+    document_hash: [u8; 32],
+    // Party that attached the document
+    submitted_by: Address,
+    // Timestamp when the document was attached
+    submitted_at: U256,
+}
+
+// Reduced platform fee for trades at or above a price threshold
+#[derive(PartialEq, Clone)]
+struct FeeTier {
+    // Minimum trade price for this tier
+    min_amount: U256,
+    // Platform fee for this tier (in basis points)
+    fee_basis_points: U256,
+}
+
+// Offer to settle a short or damaged shipment for a reduced payout
+#[derive(PartialEq, Clone)]
+struct PartialSettlementOffer {
+    // Party that made the offer (exporter offering or importer requesting)
+    proposed_by: Address,
+    // Gross amount released to the exporter; the remainder is refunded
+    exporter_amount: U256,
+    // Timestamp when the offer was made
+    proposed_at: U256,
+}
+
+// Request to push back a trade's expiry, awaiting the counterparty's acceptance
+#[derive(PartialEq, Clone)]
+struct ExtensionRequest {
+    // Party that requested the extension
+    requested_by: Address,
+    // Requested new expiration timestamp
+    new_expires_at: U256,
+    // Timestamp when the extension was requested
+    requested_at: U256,
+}
+
+// Importer's guarantor nomination awaiting the exporter's acceptance
+#[derive(PartialEq, Clone)]
+struct GuarantorNomination {
+    // Nominated guarantor (zero to remove the current guarantor)
+    guarantor: Address,
+    // Timestamp when the nomination was made (zero if none is pending)
+    nominated_at: U256,
+}
+
+// Amendment struct for proposed or approved changes to trade terms
+#[derive(PartialEq, Clone)]
+struct Amendment {
+    // Party that proposed the amendment
+    proposer: Address,
+    // Party that approved the amendment (zero while pending)
+    approver: Address,
+    // New agreed price
+    price: U256,
+    // New expiration timestamp
+    expires_at: U256,
+    // New trade description hash
+    description_hash: [u8; 32],
+    // Timestamp when the amendment was proposed
+    proposed_at: U256,
+    // Timestamp when the amendment was approved
+    approved_at: U256,
+}
+
+// Per-address trade history used as a counterparty risk signal
+#[derive(PartialEq, Clone)]
+struct PartyStats {
+    // Trades settled by delivery confirmation or dispute resolution
+    completed_trades: U256,
+    // Trades refunded after expiry
+    refunded_trades: U256,
+    // Trades that entered dispute
+    disputed_trades: U256,
+    // Disputes resolved with more than half the escrow in this party's favour
+    disputes_won: U256,
+    // Disputes resolved with more than half the escrow against this party
+    disputes_lost: U256,
+    // Total value released to exporters on this party's settled trades
+    total_volume: U256,
+    // Trades completed by delivery confirmation
+    delivered_trades: U256,
+    // Sum of funding-to-delivery times across delivered trades
+    total_delivery_time: U256,
+}
+
+// Settlement breakdown recorded when a dispute or partial settlement is resolved
+#[derive(PartialEq, Clone)]
+struct Settlement {
+    // Trade ID
+    trade_id: U256,
+    // Share of the escrow awarded to the exporter (in basis points, 10000 = 100%)
+    exporter_basis_points: U256,
+    // Net amount paid to the exporter
+    exporter_amount: U256,
+    // Amount refunded to the importer (or their guarantor)
+    importer_amount: U256,
+    // Platform fee charged on the exporter's share
+    platform_fee: U256,
+    // Arbitration fee charged on the full escrow
+    arbitration_fee: U256,
+    // Whether the admin waived platform and arbitration fees
+    fees_waived: bool,
+    // Amount paid to the importer (or their guarantor) from the insurance pool
+    insurance_payout: U256,
+    // Timestamp when the dispute was resolved
+    resolved_at: U256,
 }
 
 // Main contract implementation
@@ -64,7 +324,7 @@ impl TradeEscrow {
         self.admin.set(msg::sender());
         
         // Set the fee basis points (limit to max 1000 basis points = 10%)
-        if fee_basis_points > U256::from(1000u32) {
+        if fee_basis_points > U256::from(MAX_FEE_BASIS_POINTS) {
             return Err(b"Fee too high".to_vec());
         }
         self.fee_basis_points.set(fee_basis_points);
@@ -78,250 +338,1881 @@ impl TradeEscrow {
         Ok(())
     }
     
-    // Create a new trade escrow
+    // Set the arbitration fee charged when resolving disputes (admin)
     #[payable(false)]
-    pub fn create_trade(
-        &mut self,
-        importer: Address,
-        description_hash: [u8; 32],
-        expiry_duration: U256,
-    ) -> Result<U256, Vec<u8>> {
-        // Get exporter address (trade creator)
-        let exporter = msg::sender();
-        
-        // Ensure not sending to self
-        if exporter == importer {
-            return Err(b"Cannot trade with self".to_vec());
+    pub fn set_arbitration_fee(&mut self, arbitration_fee_basis_points: U256) -> Result<(), Vec<u8>> {
+        // Ensure sender is admin
+        if msg::sender() != self.admin.get() {
+            return Err(b"Not admin".to_vec());
         }
         
-        // Get current trade ID and increment counter
-        let trade_id = self.trade_counter.get();
-        self.trade_counter.set(trade_id + U256::from(1u32));
-        
-        // Calculate expiration timestamp
-        let current_time = block_timestamp();
-        let expires_at = current_time + expiry_duration;
-        
-        // Create new trade
-        let trade = Trade {
-            exporter,
-            importer,
-            amount: U256::ZERO,
-            status: 0, // Created
-            description_hash,
-            created_at: current_time,
-            expires_at,
-        };
-        
-        // Save trade in storage
-        self.trades.insert(trade_id, trade);
+        // Limit to max 1000 basis points = 10%
+        if arbitration_fee_basis_points > U256::from(MAX_FEE_BASIS_POINTS) {
+            return Err(b"Fee too high".to_vec());
+        }
+        self.arbitration_fee_basis_points.set(arbitration_fee_basis_points);
         
-        // Return the trade ID
-        Ok(trade_id)
+        Ok(())
     }
     
-    // Fund a trade by the importer
-    #[payable(true)]
-    pub fn fund_trade(&mut self, trade_id: U256) -> Result<(), Vec<u8>> {
-        // Get trade from storage
-        let mut trade = self.get_trade(trade_id)?;
-        
-        // Ensure sender is the importer
-        if msg::sender() != trade.importer {
-            return Err(b"Not the importer".to_vec());
+    // Set the base platform fee for new trades (admin)
+    #[payable(false)]
+    pub fn set_fee_basis_points(&mut self, fee_basis_points: U256) -> Result<(), Vec<u8>> {
+        // Ensure sender is admin
+        if msg::sender() != self.admin.get() {
+            return Err(b"Not admin".to_vec());
         }
         
-        // Ensure trade is in created status
-        if trade.status != 0 {
-            return Err(b"Invalid trade status".to_vec());
+        // Limit to max 1000 basis points = 10%
+        if fee_basis_points > U256::from(MAX_FEE_BASIS_POINTS) {
+            return Err(b"Fee too high".to_vec());
         }
+        self.fee_basis_points.set(fee_basis_points);
         
-        // Ensure not expired
-        let current_time = block_timestamp();
-        if current_time > trade.expires_at {
-            return Err(b"Trade expired".to_vec());
+        Ok(())
+    }
+    
+    // Rotate the fee collector address (admin)
+    #[payable(false)]
+    pub fn set_fee_collector(&mut self, fee_collector: Address) -> Result<(), Vec<u8>> {
+        // Ensure sender is admin
+        if msg::sender() != self.admin.get() {
+            return Err(b"Not admin".to_vec());
         }
         
-        // Set trade amount and update status
-        trade.amount = msg::value();
-        trade.status = 1; // Funded
+        if fee_collector == Address::ZERO {
+            return Err(b"Invalid fee collector".to_vec());
+        }
         
-        // Update trade in storage
-        self.trades.insert(trade_id, trade);
+        // Fees already credited stay withdrawable by the previous collector
+        self.fee_collector.set(fee_collector);
         
         Ok(())
     }
     
-    // Confirm delivery by the importer, releasing funds to exporter
+    // Append a volume fee tier with a higher threshold and lower fee than the last (admin)
     #[payable(false)]
-    pub fn confirm_delivery(&mut self, trade_id: U256) -> Result<(), Vec<u8>> {
-        // Get trade from storage
-        let mut trade = self.get_trade(trade_id)?;
-        
-        // Ensure sender is the importer
-        if msg::sender() != trade.importer {
-            return Err(b"Not the importer".to_vec());
+    pub fn add_fee_tier(&mut self, min_amount: U256, fee_basis_points: U256) -> Result<U256, Vec<u8>> {
+        // Ensure sender is admin
+        if msg::sender() != self.admin.get() {
+            return Err(b"Not admin".to_vec());
         }
         
-        // Ensure trade is in funded status
-        if trade.status != 1 {
-            return Err(b"Trade not funded".to_vec());
+        // Limit number of tiers
+        let tier_count = self.fee_tier_count.get();
+        if tier_count >= U256::from(MAX_FEE_TIERS) {
+            return Err(b"Too many fee tiers".to_vec());
         }
         
-        // Update trade status
-        trade.status = 3; // Completed
-        
-        // Calculate fee
-        let fee_basis_points = self.fee_basis_points.get();
-        let fee = (trade.amount * fee_basis_points) / U256::from(10000u32);
-        let payment_amount = trade.amount - fee;
-        
-        // Update trade in storage
-        self.trades.insert(trade_id, trade.clone());
+        // Limit to max 1000 basis points = 10%
+        if fee_basis_points > U256::from(MAX_FEE_BASIS_POINTS) {
+            return Err(b"Fee too high".to_vec());
+        }
         
-        // Transfer fee to fee collector if fee is non-zero
-        if fee > U256::ZERO {
-            let fee_collector = self.fee_collector.get();
-            if !fee_collector.transfer(fee) {
-                return Err(b"Fee transfer failed".to_vec());
+        // Keep tiers ascending by threshold and descending by fee
+        if tier_count > U256::ZERO {
+            let last = self.fee_tiers.get(tier_count - U256::from(1u32));
+            if min_amount <= last.min_amount {
+                return Err(b"Threshold not increasing".to_vec());
+            }
+            if fee_basis_points >= last.fee_basis_points {
+                return Err(b"Fee not decreasing".to_vec());
             }
         }
         
-        // Transfer payment to exporter
-        if !trade.exporter.transfer(payment_amount) {
-            return Err(b"Payment transfer failed".to_vec());
+        // Save tier
+        self.fee_tiers.insert(tier_count, FeeTier { min_amount, fee_basis_points });
+        self.fee_tier_count.set(tier_count + U256::from(1u32));
+        
+        // Return the tier index
+        Ok(tier_count)
+    }
+    
+    // Remove all volume fee tiers (admin)
+    #[payable(false)]
+    pub fn clear_fee_tiers(&mut self) -> Result<(), Vec<u8>> {
+        // Ensure sender is admin
+        if msg::sender() != self.admin.get() {
+            return Err(b"Not admin".to_vec());
         }
         
+        self.fee_tier_count.set(U256::ZERO);
+        
         Ok(())
     }
     
-    // Refund if delivery not confirmed before expiry
+    // Set the cargo insurance premium for newly funded trades (admin)
     #[payable(false)]
-    pub fn claim_refund(&mut self, trade_id: U256) -> Result<(), Vec<u8>> {
-        // Get trade from storage
-        let mut trade = self.get_trade(trade_id)?;
-        
-        // Ensure trade is in funded status
-        if trade.status != 1 {
-            return Err(b"Trade not funded".to_vec());
+    pub fn set_insurance_premium(&mut self, premium_basis_points: U256) -> Result<(), Vec<u8>> {
+        // Ensure sender is admin
+        if msg::sender() != self.admin.get() {
+            return Err(b"Not admin".to_vec());
         }
         
-        // Ensure trade has expired
-        let current_time = block_timestamp();
-        if current_time <= trade.expires_at {
-            return Err(b"Trade not expired".to_vec());
+        // Limit to max 1000 basis points = 10%
+        if premium_basis_points > U256::from(MAX_FEE_BASIS_POINTS) {
+            return Err(b"Premium too high".to_vec());
         }
+        self.insurance_premium_basis_points.set(premium_basis_points);
         
-        // Update trade status
-        trade.status = 4; // Refunded
+        Ok(())
+    }
+    
+    // Capitalize the insurance pool in exchange for pool shares (underwriter)
+    #[payable(true)]
+    pub fn deposit_insurance_capital(&mut self) -> Result<U256, Vec<u8>> {
+        let underwriter = msg::sender();
         
-        // Update trade in storage
-        self.trades.insert(trade_id, trade.clone());
+        // Ensure value is sent
+        let amount = msg::value();
+        if amount == U256::ZERO {
+            return Err(b"Zero amount".to_vec());
+        }
         
-        // Transfer full amount back to importer
-        if !trade.importer.transfer(trade.amount) {
-            return Err(b"Refund transfer failed".to_vec());
+        // A pool drained by payouts must have its worthless shares redeemed before it is recapitalized,
+        // or existing holders would take part of the new deposit
+        let pool_balance = self.insurance_pool_balance.get();
+        let total_shares = self.total_underwriter_shares.get();
+        if pool_balance == U256::ZERO && total_shares != U256::ZERO {
+            return Err(b"Pool depleted".to_vec());
         }
         
-        Ok(())
+        // Issue shares at the current pool value per share
+        let shares = if total_shares == U256::ZERO {
+            amount
+        } else {
+            (amount * total_shares) / pool_balance
+        };
+        
+        // Update pool and underwriter shares
+        self.insurance_pool_balance.set(pool_balance + amount);
+        self.total_underwriter_shares.set(total_shares + shares);
+        let underwriter_shares = self.underwriter_shares.get(underwriter);
+        self.underwriter_shares.insert(underwriter, underwriter_shares + shares);
+        
+        // Return the shares issued
+        Ok(shares)
     }
     
-    // Mark trade as disputed, only admin can resolve
+    // Redeem pool shares for unreserved capital, credited for withdrawal (underwriter)
     #[payable(false)]
-    pub fn dispute_trade(&mut self, trade_id: U256) -> Result<(), Vec<u8>> {
-        // Get trade from storage
-        let mut trade = self.get_trade(trade_id)?;
+    pub fn redeem_insurance_shares(&mut self, shares: U256) -> Result<U256, Vec<u8>> {
+        let underwriter = msg::sender();
         
-        // Ensure sender is either importer or exporter
-        let sender = msg::sender();
-        if sender != trade.importer && sender != trade.exporter {
-            return Err(b"Not a trade party".to_vec());
+        // Ensure underwriter holds enough shares
+        let underwriter_shares = self.underwriter_shares.get(underwriter);
+        if shares == U256::ZERO || shares > underwriter_shares {
+            return Err(b"Insufficient shares".to_vec());
         }
         
-        // Ensure trade is in funded status
-        if trade.status != 1 {
-            return Err(b"Trade not funded".to_vec());
+        // Value shares at the current pool value per share
+        let pool_balance = self.insurance_pool_balance.get();
+        let total_shares = self.total_underwriter_shares.get();
+        let amount = (shares * pool_balance) / total_shares;
+        
+        // Capital backing active coverage cannot be withdrawn
+        if amount > pool_balance - self.insurance_reserved.get() {
+            return Err(b"Capital reserved for coverage".to_vec());
         }
         
-        // Update trade status
-        trade.status = 5; // Disputed
+        // Update pool and underwriter shares
+        self.insurance_pool_balance.set(pool_balance - amount);
+        self.total_underwriter_shares.set(total_shares - shares);
+        self.underwriter_shares.insert(underwriter, underwriter_shares - shares);
         
-        // Update trade in storage
-        self.trades.insert(trade_id, trade);
+        // Credit capital to underwriter for withdrawal
+        self.credit(underwriter, amount);
         
-        Ok(())
+        // Return the amount redeemed
+        Ok(amount)
     }
     
-    // Admin resolves dispute by deciding where funds go
+    // Set the grace period after expiry before refunds can be claimed on new trades (admin)
     #[payable(false)]
-    pub fn resolve_dispute(
-        &mut self,
-        trade_id: U256,
-        exporter_percent: U256,
-    ) -> Result<(), Vec<u8>> {
+    pub fn set_refund_grace_period(&mut self, grace_period: U256) -> Result<(), Vec<u8>> {
         // Ensure sender is admin
         if msg::sender() != self.admin.get() {
             return Err(b"Not admin".to_vec());
         }
         
-        // Get trade from storage
-        let mut trade = self.get_trade(trade_id)?;
-        
-        // Ensure trade is in disputed status
-        if trade.status != 5 {
-            return Err(b"Trade not disputed".to_vec());
+        // Limit to max 30 days
+        if grace_period > U256::from(MAX_REFUND_GRACE_PERIOD) {
+            return Err(b"Grace period too long".to_vec());
         }
+        self.refund_grace_period.set(grace_period);
         
-        // Ensure exporter_percent is valid (0-100%)
-        if exporter_percent > U256::from(100u32) {
-            return Err(b"Invalid percentage".to_vec());
+        Ok(())
+    }
+    
+    // Create a new trade escrow
+    #[payable(false)]
+    pub fn create_trade(
+        &mut self,
+        importer: Address,
+        price: U256,
+        description_hash: [u8; 32],
+        expiry_duration: U256,
+        incoterm: u8,
+        required_documents: u32,
+    ) -> Result<U256, Vec<u8>> {
+        self.open_trade(
+            msg::sender(),
+            importer,
+            price,
+            description_hash,
+            expiry_duration,
+            incoterm,
+            required_documents,
+        )
+    }
+    
+    // Create several trades under one sales contract (e.g., one per container)
+    #[payable(false)]
+    pub fn create_trades(
+        &mut self,
+        importers: Vec<Address>,
+        prices: Vec<U256>,
+        description_hashes: Vec<[u8; 32]>,
+        expiry_duration: U256,
+        incoterm: u8,
+        required_documents: u32,
+    ) -> Result<Vec<U256>, Vec<u8>> {
+        // Validate batch
+        if importers.is_empty() || importers.len() > MAX_BATCH_SIZE {
+            return Err(b"Invalid batch size".to_vec());
         }
         
-        // Calculate amounts
-        let exporter_amount = (trade.amount * exporter_percent) / U256::from(100u32);
-        let importer_amount = trade.amount - exporter_amount;
+        if prices.len() != importers.len() || description_hashes.len() != importers.len() {
+            return Err(b"Batch length mismatch".to_vec());
+        }
         
-        // Update trade status
-        trade.status = 3; // Completed
+        // Create each trade, reverting the whole batch on any failure
+        let exporter = msg::sender();
+        let mut trade_ids = Vec::new();
+        for i in 0..importers.len() {
+            let trade_id = self.open_trade(
+                exporter,
+                importers[i],
+                prices[i],
+                description_hashes[i],
+                expiry_duration,
+                incoterm,
+                required_documents,
+            )?;
+            trade_ids.push(trade_id);
+        }
         
-        // Update trade in storage
-        self.trades.insert(trade_id, trade.clone());
+        // Return the created trade IDs
+        Ok(trade_ids)
+    }
+    
+    // Fund a trade by the importer
+    #[payable(true)]
+    pub fn fund_trade(&mut self, trade_id: U256) -> Result<(), Vec<u8>> {
+        self.fund(trade_id, msg::sender(), msg::value())
+    }
+    
+    // Fund several trades with a single payment equal to the sum of their prices
+    #[payable(true)]
+    pub fn fund_trades(&mut self, trade_ids: Vec<U256>) -> Result<(), Vec<u8>> {
+        // Validate batch
+        if trade_ids.is_empty() || trade_ids.len() > MAX_BATCH_SIZE {
+            return Err(b"Invalid batch size".to_vec());
+        }
         
-        // Transfer funds according to resolution
-        if exporter_amount > U256::ZERO {
-            if !trade.exporter.transfer(exporter_amount) {
-                return Err(b"Exporter transfer failed".to_vec());
-            }
+        // Fund each trade at its own price, reverting the whole batch on any failure
+        let funder = msg::sender();
+        let mut total = U256::ZERO;
+        for trade_id in trade_ids.iter() {
+            let amount = self.get_funding_amount(*trade_id)?;
+            self.fund(*trade_id, funder, amount)?;
+            total = total + amount;
         }
         
-        if importer_amount > U256::ZERO {
-            if !trade.importer.transfer(importer_amount) {
-                return Err(b"Importer transfer failed".to_vec());
-            }
+        // Ensure payment covers exactly the funded trades
+        if msg::value() != total {
+            return Err(b"Incorrect payment amount".to_vec());
         }
         
         Ok(())
     }
     
-    // Get trade details by ID
-    #[payable(false)]
-    pub fn get_trade(&self, trade_id: U256) -> Result<Trade, Vec<u8>> {
-        let trade = self.trades.get(trade_id);
+    // Create and fund a trade from an exporter's EIP-712 signed offer (importer)
+    #[payable(true)]
+    pub fn fund_signed_offer(
+        &mut self,
+        exporter: Address,
+        price: U256,
+        description_hash: [u8; 32],
+        expiry_duration: U256,
+        incoterm: u8,
+        required_documents: u32,
+        nonce: U256,
+        deadline: U256,
+        v: u8,
+        r: [u8; 32],
+        s: [u8; 32],
+    ) -> Result<U256, Vec<u8>> {
+        // Importer is the submitter, and is part of the signed terms
+        let importer = msg::sender();
         
-        // Ensure trade exists (check if exporter is non-zero)
-        if trade.exporter == Address::ZERO {
-            return Err(b"Trade not found".to_vec());
+        // Ensure offer is still valid
+        if block_timestamp() > deadline {
+            return Err(b"Offer expired".to_vec());
         }
         
-        Ok(trade)
-    }
-    
-    // Get trade status by ID
-    #[payable(false)]
-    pub fn get_trade_status(&self, trade_id: U256) -> Result<u8, Vec<u8>> {
+        // Ensure nonce has not been used or cancelled
+        if self.used_offer_nonces.get(exporter).get(nonce) {
+            return Err(b"Nonce already used".to_vec());
+        }
+        
+        // Ensure the exporter signed these exact terms for this importer
+        let digest = self.get_offer_digest(
+            exporter,
+            importer,
+            price,
+            description_hash,
+            expiry_duration,
+            incoterm,
+            required_documents,
+            nonce,
+            deadline,
+        );
+        if Self::recover_signer(digest, v, r, s)? != exporter {
+            return Err(b"Invalid signature".to_vec());
+        }
+        
+        // Consume nonce to prevent replay
+        self.used_offer_nonces.get(exporter).insert(nonce, true);
+        
+        // Create and fund the trade in one step
+        let trade_id = self.open_trade(
+            exporter,
+            importer,
+            price,
+            description_hash,
+            expiry_duration,
+            incoterm,
+            required_documents,
+        )?;
+        self.fund(trade_id, importer, msg::value())?;
+        
+        // Return the trade ID
+        Ok(trade_id)
+    }
+    
+    // Cancel an unused signed-offer nonce so the offer can no longer be submitted (exporter)
+    #[payable(false)]
+    pub fn cancel_offer_nonce(&mut self, nonce: U256) -> Result<(), Vec<u8>> {
+        let exporter = msg::sender();
+        
+        // Ensure nonce has not been used or cancelled
+        if self.used_offer_nonces.get(exporter).get(nonce) {
+            return Err(b"Nonce already used".to_vec());
+        }
+        
+        self.used_offer_nonces.get(exporter).insert(nonce, true);
+        
+        Ok(())
+    }
+    
+    // Nominate an issuing bank/guarantor to fund the trade (importer, before funding);
+    // takes effect once the exporter accepts
+    #[payable(false)]
+    pub fn set_guarantor(&mut self, trade_id: U256, guarantor: Address) -> Result<(), Vec<u8>> {
+        // Get trade from storage
+        let trade = self.get_trade(trade_id)?;
+        
+        // Ensure sender is the importer
+        if msg::sender() != trade.importer {
+            return Err(b"Not the importer".to_vec());
+        }
+        
+        // Ensure trade is in created status
+        if trade.status != 0 {
+            return Err(b"Invalid trade status".to_vec());
+        }
+        
+        // Guarantor must be independent of the trade parties
+        if guarantor == trade.exporter || guarantor == trade.importer {
+            return Err(b"Invalid guarantor".to_vec());
+        }
+        
+        // Store as pending, replacing any earlier nomination (zero address removes the guarantor)
+        let nomination = GuarantorNomination {
+            guarantor,
+            nominated_at: block_timestamp(),
+        };
+        self.pending_guarantors.insert(trade_id, nomination);
+        
+        Ok(())
+    }
+    
+    // Accept the importer's guarantor nomination; the expected guarantor is passed in so a
+    // replacement nomination can't slip in ahead of the acceptance
+    #[payable(false)]
+    pub fn accept_guarantor(&mut self, trade_id: U256, guarantor: Address) -> Result<(), Vec<u8>> {
+        // Get trade from storage
+        let mut trade = self.get_trade(trade_id)?;
+        
+        // Ensure sender is the exporter
+        if msg::sender() != trade.exporter {
+            return Err(b"Not the exporter".to_vec());
+        }
+        
+        // Ensure trade is in created status
+        if trade.status != 0 {
+            return Err(b"Invalid trade status".to_vec());
+        }
+        
+        // Ensure the pending nomination is the one the exporter reviewed
+        let nomination = self.get_pending_guarantor(trade_id)?;
+        if nomination.guarantor != guarantor {
+            return Err(b"Nomination changed".to_vec());
+        }
+        
+        // Set guarantor (zero address removes it); a new funding party must accept any insurance premium
+        trade.guarantor = guarantor;
+        trade.guarantor_approved = false;
+        trade.insured = false;
+        
+        // Update trade in storage and clear pending
+        self.trades.insert(trade_id, trade);
+        self.pending_guarantors.delete(trade_id);
+        
+        Ok(())
+    }
+    
+    // Request cargo insurance before funding (exporter); cover applies once the funding party accepts
+    #[payable(false)]
+    pub fn enable_insurance(&mut self, trade_id: U256) -> Result<(), Vec<u8>> {
+        // Get trade from storage
+        let mut trade = self.get_trade(trade_id)?;
+        
+        // Ensure sender is the exporter
+        if msg::sender() != trade.exporter {
+            return Err(b"Not the exporter".to_vec());
+        }
+        
+        // Ensure trade is in created status
+        if trade.status != 0 {
+            return Err(b"Invalid trade status".to_vec());
+        }
+        
+        // Record the request; the funding party pays the premium, so it must accept
+        trade.insurance_requested = true;
+        
+        // Update trade in storage
+        self.trades.insert(trade_id, trade);
+        
+        Ok(())
+    }
+    
+    // Accept the exporter's insurance request, agreeing to pay the premium at funding (funding party)
+    #[payable(false)]
+    pub fn accept_insurance(&mut self, trade_id: U256) -> Result<(), Vec<u8>> {
+        // Get trade from storage
+        let mut trade = self.get_trade(trade_id)?;
+        
+        // Ensure sender is the funding party (guarantor if nominated, otherwise importer)
+        if msg::sender() != Self::funding_party(&trade) {
+            return Err(b"Not the funding party".to_vec());
+        }
+        
+        // Ensure trade is in created status
+        if trade.status != 0 {
+            return Err(b"Invalid trade status".to_vec());
+        }
+        
+        if !trade.insurance_requested {
+            return Err(b"Insurance not requested".to_vec());
+        }
+        
+        // Mark trade as insured; premium is charged at funding
+        trade.insured = true;
+        
+        // Update trade in storage
+        self.trades.insert(trade_id, trade);
+        
+        Ok(())
+    }
+    
+    // Guarantor approves that presented documents comply with the credit terms
+    #[payable(false)]
+    pub fn approve_documents(&mut self, trade_id: U256) -> Result<(), Vec<u8>> {
+        // Get trade from storage
+        let mut trade = self.get_trade(trade_id)?;
+        
+        // Ensure sender is the guarantor
+        if trade.guarantor == Address::ZERO || msg::sender() != trade.guarantor {
+            return Err(b"Not the guarantor".to_vec());
+        }
+        
+        // Ensure trade is in funded status
+        if trade.status != 1 {
+            return Err(b"Trade not funded".to_vec());
+        }
+        
+        // Ensure all required documents have been attached
+        if !self.has_required_documents(trade_id)? {
+            return Err(b"Missing required documents".to_vec());
+        }
+        
+        // Record approval
+        trade.guarantor_approved = true;
+        
+        // Update trade in storage
+        self.trades.insert(trade_id, trade);
+        
+        Ok(())
+    }
+    
+    // Cancel a trade before it is funded (exporter or importer)
+    #[payable(false)]
+    pub fn cancel_trade(&mut self, trade_id: U256) -> Result<(), Vec<u8>> {
+        // Get trade from storage
+        let mut trade = self.get_trade(trade_id)?;
+        
+        // Ensure sender is either importer or exporter
+        let sender = msg::sender();
+        if sender != trade.importer && sender != trade.exporter {
+            return Err(b"Not a trade party".to_vec());
+        }
+        
+        // Ensure trade is in created status
+        if trade.status != 0 {
+            return Err(b"Invalid trade status".to_vec());
+        }
+        
+        // Update trade status
+        trade.status = 6; // Cancelled
+        
+        // Update trade in storage
+        self.trades.insert(trade_id, trade);
+        
+        // Discard any pending amendment
+        self.pending_amendments.delete(trade_id);
+        
+        Ok(())
+    }
+    
+    // Propose new trade terms before funding (exporter or importer)
+    #[payable(false)]
+    pub fn propose_amendment(
+        &mut self,
+        trade_id: U256,
+        price: U256,
+        expires_at: U256,
+        description_hash: [u8; 32],
+    ) -> Result<(), Vec<u8>> {
+        // Get trade from storage
+        let trade = self.get_trade(trade_id)?;
+        
+        // Ensure sender is either importer or exporter
+        let sender = msg::sender();
+        if sender != trade.importer && sender != trade.exporter {
+            return Err(b"Not a trade party".to_vec());
+        }
+        
+        // Ensure trade is in created status
+        if trade.status != 0 {
+            return Err(b"Invalid trade status".to_vec());
+        }
+        
+        // Validate new terms
+        if price == U256::ZERO {
+            return Err(b"Zero price".to_vec());
+        }
+        
+        let current_time = block_timestamp();
+        if expires_at <= current_time {
+            return Err(b"Expiry in the past".to_vec());
+        }
+        
+        // Store as pending, replacing any earlier proposal
+        let amendment = Amendment {
+            proposer: sender,
+            approver: Address::ZERO,
+            price,
+            expires_at,
+            description_hash,
+            proposed_at: current_time,
+            approved_at: U256::ZERO,
+        };
+        self.pending_amendments.insert(trade_id, amendment);
+        
+        Ok(())
+    }
+    
+    // Approve the counterparty's pending amendment, applying the new terms; the expected terms
+    // are passed in so a replacement proposal can't slip in ahead of the approval
+    #[payable(false)]
+    pub fn approve_amendment(
+        &mut self,
+        trade_id: U256,
+        price: U256,
+        expires_at: U256,
+        description_hash: [u8; 32],
+    ) -> Result<(), Vec<u8>> {
+        // Get trade from storage
+        let mut trade = self.get_trade(trade_id)?;
+        
+        // Ensure sender is either importer or exporter
+        let sender = msg::sender();
+        if sender != trade.importer && sender != trade.exporter {
+            return Err(b"Not a trade party".to_vec());
+        }
+        
+        // Ensure trade is in created status
+        if trade.status != 0 {
+            return Err(b"Invalid trade status".to_vec());
+        }
+        
+        // Get pending amendment
+        let mut amendment = self.get_pending_amendment(trade_id)?;
+        
+        // Both parties must sign off, so the proposer cannot approve
+        if sender == amendment.proposer {
+            return Err(b"Proposer cannot approve".to_vec());
+        }
+        
+        // Ensure the pending terms are the ones the approver reviewed
+        if amendment.price != price || amendment.expires_at != expires_at || amendment.description_hash != description_hash {
+            return Err(b"Amendment changed".to_vec());
+        }
+        
+        // Re-quote the fee for a new price, so amending the price can't keep a tier it no longer reaches
+        if amendment.price != trade.price {
+            trade.fee_basis_points = self.quote_fee_basis_points(amendment.price);
+        }
+        
+        // Apply the new terms
+        trade.price = amendment.price;
+        trade.expires_at = amendment.expires_at;
+        trade.description_hash = amendment.description_hash;
+        
+        // Update trade in storage
+        self.trades.insert(trade_id, trade);
+        
+        // Record approval
+        amendment.approver = sender;
+        amendment.approved_at = block_timestamp();
+        
+        // Add to amendment history and clear pending
+        let amendment_count = self.amendment_counts.get(trade_id);
+        self.amendment_history.get(trade_id).insert(amendment_count, amendment);
+        self.amendment_counts.insert(trade_id, amendment_count + U256::from(1u32));
+        self.pending_amendments.delete(trade_id);
+        
+        Ok(())
+    }
+    
+    // Withdraw or reject a pending amendment (exporter or importer)
+    #[payable(false)]
+    pub fn reject_amendment(&mut self, trade_id: U256) -> Result<(), Vec<u8>> {
+        // Get trade from storage
+        let trade = self.get_trade(trade_id)?;
+        
+        // Ensure sender is either importer or exporter
+        let sender = msg::sender();
+        if sender != trade.importer && sender != trade.exporter {
+            return Err(b"Not a trade party".to_vec());
+        }
+        
+        // Ensure an amendment is pending
+        self.get_pending_amendment(trade_id)?;
+        
+        // Clear pending amendment
+        self.pending_amendments.delete(trade_id);
+        
+        Ok(())
+    }
+    
+    // Request a later expiry, e.g. for shipping delays (exporter or importer)
+    #[payable(false)]
+    pub fn request_extension(&mut self, trade_id: U256, new_expires_at: U256) -> Result<(), Vec<u8>> {
+        // Get trade from storage
+        let trade = self.get_trade(trade_id)?;
+        
+        // Ensure sender is either importer or exporter
+        let sender = msg::sender();
+        if sender != trade.importer && sender != trade.exporter {
+            return Err(b"Not a trade party".to_vec());
+        }
+        
+        // Ensure trade is in created or funded status
+        if trade.status != 0 && trade.status != 1 {
+            return Err(b"Invalid trade status".to_vec());
+        }
+        
+        // Extensions can only push expiry back
+        if new_expires_at <= trade.expires_at {
+            return Err(b"Expiry not extended".to_vec());
+        }
+        
+        // Store as pending, replacing any earlier request
+        let request = ExtensionRequest {
+            requested_by: sender,
+            new_expires_at,
+            requested_at: block_timestamp(),
+        };
+        self.pending_extensions.insert(trade_id, request);
+        
+        Ok(())
+    }
+    
+    // Accept the counterparty's extension request, applying the new expiry; the expected expiry
+    // is passed in so a replacement request can't slip in ahead of the acceptance
+    #[payable(false)]
+    pub fn accept_extension(&mut self, trade_id: U256, new_expires_at: U256) -> Result<(), Vec<u8>> {
+        // Get trade from storage
+        let mut trade = self.get_trade(trade_id)?;
+        
+        // Ensure sender is either importer or exporter
+        let sender = msg::sender();
+        if sender != trade.importer && sender != trade.exporter {
+            return Err(b"Not a trade party".to_vec());
+        }
+        
+        // Ensure trade is still in created or funded status
+        if trade.status != 0 && trade.status != 1 {
+            return Err(b"Invalid trade status".to_vec());
+        }
+        
+        // Get pending extension
+        let request = self.get_pending_extension(trade_id)?;
+        
+        // Both parties must consent, so the requester cannot accept
+        if sender == request.requested_by {
+            return Err(b"Requester cannot accept".to_vec());
+        }
+        
+        // Ensure the pending expiry is the one the accepter reviewed
+        if request.new_expires_at != new_expires_at {
+            return Err(b"Extension changed".to_vec());
+        }
+        
+        // Guard against a request made stale by an amendment
+        if request.new_expires_at <= trade.expires_at {
+            return Err(b"Expiry not extended".to_vec());
+        }
+        
+        // Apply the new expiry
+        trade.expires_at = request.new_expires_at;
+        
+        // Update trade in storage and clear pending
+        self.trades.insert(trade_id, trade);
+        self.pending_extensions.delete(trade_id);
+        
+        Ok(())
+    }
+    
+    // Confirm delivery by the importer, releasing funds to exporter
+    #[payable(false)]
+    pub fn confirm_delivery(&mut self, trade_id: U256) -> Result<(), Vec<u8>> {
+        // Get trade from storage
+        let mut trade = self.get_trade(trade_id)?;
+        
+        // Ensure sender is the importer
+        if msg::sender() != trade.importer {
+            return Err(b"Not the importer".to_vec());
+        }
+        
+        // Ensure trade is in funded status
+        if trade.status != 1 {
+            return Err(b"Trade not funded".to_vec());
+        }
+        
+        // Ensure all required documents have been attached
+        if !self.has_required_documents(trade_id)? {
+            return Err(b"Missing required documents".to_vec());
+        }
+        
+        // Ensure the guarantor, if any, has co-approved the documents
+        if trade.guarantor != Address::ZERO && !trade.guarantor_approved {
+            return Err(b"Guarantor approval required".to_vec());
+        }
+        
+        // Update trade status
+        trade.status = 3; // Completed
+        
+        // Calculate fee
+        let fee = (trade.amount * trade.fee_basis_points) / U256::from(10000u32);
+        let payment_amount = trade.amount - fee;
+        
+        // Update trade in storage
+        self.trades.insert(trade_id, trade.clone());
+        
+        // Release insurance coverage
+        self.release_coverage(&trade);
+        
+        // Update both parties' statistics
+        let delivery_time = block_timestamp() - trade.funded_at;
+        self.record_delivery(trade.exporter, trade.amount, delivery_time);
+        self.record_delivery(trade.importer, trade.amount, delivery_time);
+        
+        // Credit fee to fee collector and payment to exporter for withdrawal
+        let fee_collector = self.fee_collector.get();
+        self.credit(fee_collector, fee);
+        self.credit(trade.exporter, payment_amount);
+        
+        Ok(())
+    }
+    
+    // Attach a trade document hash (exporter or importer)
+    #[payable(false)]
+    pub fn attach_document(
+        &mut self,
+        trade_id: U256,
+        doc_type: u8,
+        document_hash: [u8; 32],
+    ) -> Result<U256, Vec<u8>> {
+        // Get trade from storage
+        let trade = self.get_trade(trade_id)?;
+        
+        // Ensure sender is either importer or exporter
+        let sender = msg::sender();
+        if sender != trade.importer && sender != trade.exporter {
+            return Err(b"Not a trade party".to_vec());
+        }
+        
+        // Documents can only be attached while the trade is open (created, funded or disputed)
+        if trade.status != 0 && trade.status != 1 && trade.status != 5 {
+            return Err(b"Invalid trade status".to_vec());
+        }
+        
+        // Validate document
+        if doc_type > MAX_DOCUMENT_TYPE {
+            return Err(b"Invalid document type".to_vec());
+        }
+        
+        if document_hash == [0; 32] {
+            return Err(b"Empty document hash".to_vec());
+        }
+        
+        // Create document record
+        let document = TradeDocument {
+            doc_type,
+            document_hash,
+            submitted_by: sender,
+            submitted_at: block_timestamp(),
+        };
+        
+        // Get current document count and increment
+        let document_id = self.document_counts.get(trade_id);
+        self.documents.get(trade_id).insert(document_id, document);
+        self.document_counts.insert(trade_id, document_id + U256::from(1u32));
+        
+        // Mark document type as present
+        let attached = self.attached_document_types.get(trade_id);
+        self.attached_document_types.insert(trade_id, attached | (1u32 << doc_type));
+        
+        // Return the document ID
+        Ok(document_id)
+    }
+    
+    // Refund if delivery not confirmed before expiry
+    #[payable(false)]
+    pub fn claim_refund(&mut self, trade_id: U256) -> Result<(), Vec<u8>> {
+        // Get trade from storage
+        let mut trade = self.get_trade(trade_id)?;
+        
+        // Ensure trade is in funded status
+        if trade.status != 1 {
+            return Err(b"Trade not funded".to_vec());
+        }
+        
+        // Ensure trade has expired and the grace period has passed
+        let current_time = block_timestamp();
+        if current_time <= trade.expires_at {
+            return Err(b"Trade not expired".to_vec());
+        }
+        
+        if current_time <= trade.expires_at + trade.refund_grace_period {
+            return Err(b"Grace period not over".to_vec());
+        }
+        
+        // Update trade status
+        trade.status = 4; // Refunded
+        
+        // Discard any pending extension
+        self.pending_extensions.delete(trade_id);
+        
+        // Update trade in storage
+        self.trades.insert(trade_id, trade.clone());
+        
+        // Release insurance coverage
+        self.release_coverage(&trade);
+        
+        // Update both parties' statistics
+        self.record_refund(trade.exporter);
+        self.record_refund(trade.importer);
+        
+        // Credit full amount back to the funding party for withdrawal
+        self.credit(Self::funding_party(&trade), trade.amount);
+        
+        Ok(())
+    }
+    
+    // Mark trade as disputed, only admin can resolve
+    #[payable(false)]
+    pub fn dispute_trade(&mut self, trade_id: U256) -> Result<(), Vec<u8>> {
+        // Get trade from storage
+        let mut trade = self.get_trade(trade_id)?;
+        
+        // Ensure sender is the importer, exporter or guarantor
+        let sender = msg::sender();
+        if sender != trade.importer && sender != trade.exporter
+            && (trade.guarantor == Address::ZERO || sender != trade.guarantor)
+        {
+            return Err(b"Not a trade party".to_vec());
+        }
+        
+        // Ensure trade is in funded status
+        if trade.status != 1 {
+            return Err(b"Trade not funded".to_vec());
+        }
+        
+        // Update trade status
+        trade.status = 5; // Disputed
+        
+        // Update trade in storage
+        self.trades.insert(trade_id, trade.clone());
+        
+        // Update both parties' statistics
+        self.record_dispute(trade.exporter);
+        self.record_dispute(trade.importer);
+        
+        Ok(())
+    }
+    
+    // Admin resolves dispute by deciding where funds go
+    #[payable(false)]
+    pub fn resolve_dispute(
+        &mut self,
+        trade_id: U256,
+        exporter_basis_points: U256,
+        waive_fees: bool,
+    ) -> Result<Settlement, Vec<u8>> {
+        // Ensure sender is admin
+        if msg::sender() != self.admin.get() {
+            return Err(b"Not admin".to_vec());
+        }
+        
+        // Get trade from storage
+        let mut trade = self.get_trade(trade_id)?;
+        
+        // Ensure trade is in disputed status
+        if trade.status != 5 {
+            return Err(b"Trade not disputed".to_vec());
+        }
+        
+        // Ensure exporter_basis_points is valid (0-10000 = 0-100%)
+        if exporter_basis_points > U256::from(10000u32) {
+            return Err(b"Invalid basis points".to_vec());
+        }
+        
+        // Arbitration fee comes off the full escrow before it is split
+        let arbitration_fee = if waive_fees {
+            U256::ZERO
+        } else {
+            (trade.amount * self.arbitration_fee_basis_points.get()) / U256::from(10000u32)
+        };
+        let distributable = trade.amount - arbitration_fee;
+        
+        // Split the remainder between the parties
+        let exporter_share = (distributable * exporter_basis_points) / U256::from(10000u32);
+        let importer_amount = distributable - exporter_share;
+        
+        // Platform fee applies to the exporter's share, as in confirm_delivery
+        let platform_fee = if waive_fees {
+            U256::ZERO
+        } else {
+            (exporter_share * trade.fee_basis_points) / U256::from(10000u32)
+        };
+        let exporter_amount = exporter_share - platform_fee;
+        
+        // Update trade status
+        trade.status = 3; // Completed
+        
+        // Update trade in storage
+        self.trades.insert(trade_id, trade.clone());
+        
+        // Record settlement
+        let settlement = Settlement {
+            trade_id,
+            exporter_basis_points,
+            exporter_amount,
+            importer_amount,
+            platform_fee,
+            arbitration_fee,
+            fees_waived: waive_fees,
+            insurance_payout: U256::ZERO,
+            resolved_at: block_timestamp(),
+        };
+        self.settlements.insert(trade_id, settlement.clone());
+        
+        // Release insurance coverage
+        self.release_coverage(&trade);
+        
+        // Update both parties' statistics (an even split counts as neither a win nor a loss)
+        let half = U256::from(5000u32);
+        let exporter_won = exporter_basis_points > half;
+        let importer_won = exporter_basis_points < half;
+        self.record_resolution(trade.exporter, exporter_share, exporter_won, importer_won);
+        self.record_resolution(trade.importer, exporter_share, importer_won, exporter_won);
+        
+        // Credit platform and arbitration fees to fee collector
+        let fee_collector = self.fee_collector.get();
+        self.credit(fee_collector, platform_fee + arbitration_fee);
+        
+        // Credit funds according to resolution for withdrawal
+        self.credit(trade.exporter, exporter_amount);
+        self.credit(Self::funding_party(&trade), importer_amount);
+        
+        Ok(settlement)
+    }
+    
+    // Offer (exporter) or request (importer) a reduced payout for a short or damaged shipment
+    #[payable(false)]
+    pub fn propose_partial_settlement(&mut self, trade_id: U256, exporter_amount: U256) -> Result<(), Vec<u8>> {
+        // Get trade from storage
+        let trade = self.get_trade(trade_id)?;
+        
+        // Ensure sender is either importer or exporter
+        let sender = msg::sender();
+        if sender != trade.importer && sender != trade.exporter {
+            return Err(b"Not a trade party".to_vec());
+        }
+        
+        // Ensure trade is in funded or disputed status
+        if trade.status != 1 && trade.status != 5 {
+            return Err(b"Invalid trade status".to_vec());
+        }
+        
+        // Ensure the payout is an actual reduction
+        if exporter_amount >= trade.amount {
+            return Err(b"Not a partial amount".to_vec());
+        }
+        
+        // Store as pending, replacing any earlier offer
+        let offer = PartialSettlementOffer {
+            proposed_by: sender,
+            exporter_amount,
+            proposed_at: block_timestamp(),
+        };
+        self.pending_partial_settlements.insert(trade_id, offer);
+        
+        Ok(())
+    }
+    
+    // Accept the counterparty's partial settlement offer, releasing the agreed split; the expected
+    // payout is passed in so a replacement offer can't slip in ahead of the acceptance
+    #[payable(false)]
+    pub fn accept_partial_settlement(&mut self, trade_id: U256, exporter_amount: U256) -> Result<Settlement, Vec<u8>> {
+        // Get trade from storage
+        let mut trade = self.get_trade(trade_id)?;
+        
+        // Ensure sender is either importer or exporter
+        let sender = msg::sender();
+        if sender != trade.importer && sender != trade.exporter {
+            return Err(b"Not a trade party".to_vec());
+        }
+        
+        // Ensure trade is still in funded or disputed status
+        if trade.status != 1 && trade.status != 5 {
+            return Err(b"Invalid trade status".to_vec());
+        }
+        
+        // Get pending offer
+        let offer = self.get_pending_partial_settlement(trade_id)?;
+        
+        // Both parties must agree, so the proposer cannot accept
+        if sender == offer.proposed_by {
+            return Err(b"Proposer cannot accept".to_vec());
+        }
+        
+        // Ensure the pending payout is the one the accepter reviewed
+        if offer.exporter_amount != exporter_amount {
+            return Err(b"Offer changed".to_vec());
+        }
+        
+        // Releasing funds to the exporter needs the same gates as confirm_delivery
+        if !self.has_required_documents(trade_id)? {
+            return Err(b"Missing required documents".to_vec());
+        }
+        
+        if trade.guarantor != Address::ZERO && !trade.guarantor_approved {
+            return Err(b"Guarantor approval required".to_vec());
+        }
+        
+        // Platform fee applies to the exporter's share, as in confirm_delivery
+        let exporter_share = offer.exporter_amount;
+        let importer_amount = trade.amount - exporter_share;
+        let platform_fee = (exporter_share * trade.fee_basis_points) / U256::from(10000u32);
+        let exporter_amount = exporter_share - platform_fee;
+        
+        // Update trade status
+        trade.status = 7; // Partially settled
+        
+        // Update trade in storage and clear pending requests
+        self.trades.insert(trade_id, trade.clone());
+        self.pending_partial_settlements.delete(trade_id);
+        self.pending_extensions.delete(trade_id);
+        
+        // Record settlement
+        let settlement = Settlement {
+            trade_id,
+            exporter_basis_points: (exporter_share * U256::from(10000u32)) / trade.amount,
+            exporter_amount,
+            importer_amount,
+            platform_fee,
+            arbitration_fee: U256::ZERO,
+            fees_waived: false,
+            insurance_payout: U256::ZERO,
+            resolved_at: block_timestamp(),
+        };
+        self.settlements.insert(trade_id, settlement.clone());
+        
+        // Release insurance coverage
+        self.release_coverage(&trade);
+        
+        // Update both parties' statistics (a negotiated settlement is neither a win nor a loss)
+        self.record_resolution(trade.exporter, exporter_share, false, false);
+        self.record_resolution(trade.importer, exporter_share, false, false);
+        
+        // Credit fee, payment and refund for withdrawal
+        let fee_collector = self.fee_collector.get();
+        self.credit(fee_collector, platform_fee);
+        self.credit(trade.exporter, exporter_amount);
+        self.credit(Self::funding_party(&trade), importer_amount);
+        
+        Ok(settlement)
+    }
+    
+    // Withdraw or reject a pending partial settlement offer (exporter or importer)
+    #[payable(false)]
+    pub fn reject_partial_settlement(&mut self, trade_id: U256) -> Result<(), Vec<u8>> {
+        // Get trade from storage
+        let trade = self.get_trade(trade_id)?;
+        
+        // Ensure sender is either importer or exporter
+        let sender = msg::sender();
+        if sender != trade.importer && sender != trade.exporter {
+            return Err(b"Not a trade party".to_vec());
+        }
+        
+        // Ensure an offer is pending
+        self.get_pending_partial_settlement(trade_id)?;
+        
+        // Clear pending offer
+        self.pending_partial_settlements.delete(trade_id);
+        
+        Ok(())
+    }
+    
+    // Admin resolves an insured dispute as loss in transit: the exporter is paid
+    // and the importer is compensated from the insurance pool
+    #[payable(false)]
+    pub fn resolve_loss_in_transit(&mut self, trade_id: U256) -> Result<Settlement, Vec<u8>> {
+        // Ensure sender is admin
+        if msg::sender() != self.admin.get() {
+            return Err(b"Not admin".to_vec());
+        }
+        
+        // Get trade from storage
+        let mut trade = self.get_trade(trade_id)?;
+        
+        // Ensure trade is in disputed status
+        if trade.status != 5 {
+            return Err(b"Trade not disputed".to_vec());
+        }
+        
+        // Ensure trade is insured
+        if !trade.insured {
+            return Err(b"Trade not insured".to_vec());
+        }
+        
+        // Exporter is paid as on delivery, less the platform fee
+        let platform_fee = (trade.amount * trade.fee_basis_points) / U256::from(10000u32);
+        let exporter_amount = trade.amount - platform_fee;
+        
+        // Coverage is paid out of the pool
+        let insurance_payout = trade.amount;
+        
+        // Update trade status
+        trade.status = 3; // Completed
+        
+        // Update trade in storage
+        self.trades.insert(trade_id, trade.clone());
+        
+        // Record settlement
+        let settlement = Settlement {
+            trade_id,
+            exporter_basis_points: U256::from(10000u32),
+            exporter_amount,
+            importer_amount: U256::ZERO,
+            platform_fee,
+            arbitration_fee: U256::ZERO,
+            fees_waived: false,
+            insurance_payout,
+            resolved_at: block_timestamp(),
+        };
+        self.settlements.insert(trade_id, settlement.clone());
+        
+        // Release coverage and pay the claim from the pool
+        self.release_coverage(&trade);
+        self.insurance_pool_balance.set(self.insurance_pool_balance.get() - insurance_payout);
+        
+        // Update both parties' statistics (a covered loss is neither a win nor a loss)
+        self.record_resolution(trade.exporter, trade.amount, false, false);
+        self.record_resolution(trade.importer, trade.amount, false, false);
+        
+        // Credit fee, payment and claim for withdrawal
+        let fee_collector = self.fee_collector.get();
+        self.credit(fee_collector, platform_fee);
+        self.credit(trade.exporter, exporter_amount);
+        self.credit(Self::funding_party(&trade), insurance_payout);
+        
+        Ok(settlement)
+    }
+    
+    // Withdraw all settled funds credited to the sender
+    #[payable(false)]
+    pub fn withdraw(&mut self) -> Result<U256, Vec<u8>> {
+        let sender = msg::sender();
+        
+        // Ensure there is something to withdraw
+        let amount = self.balances.get(sender);
+        if amount == U256::ZERO {
+            return Err(b"Nothing to withdraw".to_vec());
+        }
+        
+        // Clear balance before transferring
+        self.balances.insert(sender, U256::ZERO);
+        
+        // Transfer funds to sender
+        if !sender.transfer(amount) {
+            return Err(b"Withdrawal transfer failed".to_vec());
+        }
+        
+        // Return the amount withdrawn
+        Ok(amount)
+    }
+    
+    // Get settled funds available for withdrawal
+    #[payable(false)]
+    pub fn get_balance(&self, account: Address) -> U256 {
+        self.balances.get(account)
+    }
+    
+    // Get trade details by ID
+    #[payable(false)]
+    pub fn get_trade(&self, trade_id: U256) -> Result<Trade, Vec<u8>> {
+        let trade = self.trades.get(trade_id);
+        
+        // Ensure trade exists (check if exporter is non-zero)
+        if trade.exporter == Address::ZERO {
+            return Err(b"Trade not found".to_vec());
+        }
+        
+        Ok(trade)
+    }
+    
+    // Get trade status by ID
+    #[payable(false)]
+    pub fn get_trade_status(&self, trade_id: U256) -> Result<u8, Vec<u8>> {
         let trade = self.get_trade(trade_id)?;
         Ok(trade.status)
     }
+    
+    // Get the pending amendment for a trade
+    #[payable(false)]
+    pub fn get_pending_amendment(&self, trade_id: U256) -> Result<Amendment, Vec<u8>> {
+        let amendment = self.pending_amendments.get(trade_id);
+        
+        // Ensure amendment exists (check if proposer is non-zero)
+        if amendment.proposer == Address::ZERO {
+            return Err(b"No pending amendment".to_vec());
+        }
+        
+        Ok(amendment)
+    }
+    
+    // Get the pending expiry extension request for a trade
+    #[payable(false)]
+    pub fn get_pending_extension(&self, trade_id: U256) -> Result<ExtensionRequest, Vec<u8>> {
+        let request = self.pending_extensions.get(trade_id);
+        
+        // Ensure request exists (check if requester is non-zero)
+        if request.requested_by == Address::ZERO {
+            return Err(b"No pending extension".to_vec());
+        }
+        
+        Ok(request)
+    }
+    
+    // Get the guarantor nomination awaiting the exporter's acceptance
+    #[payable(false)]
+    pub fn get_pending_guarantor(&self, trade_id: U256) -> Result<GuarantorNomination, Vec<u8>> {
+        let nomination = self.pending_guarantors.get(trade_id);
+        
+        // Ensure nomination exists (check if nomination time is non-zero)
+        if nomination.nominated_at == U256::ZERO {
+            return Err(b"No pending guarantor".to_vec());
+        }
+        
+        Ok(nomination)
+    }
+    
+    // Get the timestamp after which a refund can be claimed
+    #[payable(false)]
+    pub fn get_refund_available_at(&self, trade_id: U256) -> Result<U256, Vec<u8>> {
+        let trade = self.get_trade(trade_id)?;
+        Ok(trade.expires_at + trade.refund_grace_period + U256::from(1u32))
+    }
+    
+    // Get an approved amendment from trade history
+    #[payable(false)]
+    pub fn get_amendment(&self, trade_id: U256, amendment_id: U256) -> Result<Amendment, Vec<u8>> {
+        // Ensure amendment ID is valid
+        let amendment_count = self.amendment_counts.get(trade_id);
+        if amendment_id >= amendment_count {
+            return Err(b"Amendment not found".to_vec());
+        }
+        
+        Ok(self.amendment_history.get(trade_id).get(amendment_id))
+    }
+    
+    // Get total approved amendments for a trade
+    #[payable(false)]
+    pub fn get_amendment_count(&self, trade_id: U256) -> U256 {
+        self.amendment_counts.get(trade_id)
+    }
+    
+    // Get a document attached to a trade
+    #[payable(false)]
+    pub fn get_document(&self, trade_id: U256, document_id: U256) -> Result<TradeDocument, Vec<u8>> {
+        // Ensure document ID is valid
+        let document_count = self.document_counts.get(trade_id);
+        if document_id >= document_count {
+            return Err(b"Document not found".to_vec());
+        }
+        
+        Ok(self.documents.get(trade_id).get(document_id))
+    }
+    
+    // Get total documents attached to a trade
+    #[payable(false)]
+    pub fn get_document_count(&self, trade_id: U256) -> U256 {
+        self.document_counts.get(trade_id)
+    }
+    
+    // Get bitmask of document types attached to a trade
+    #[payable(false)]
+    pub fn get_attached_document_types(&self, trade_id: U256) -> u32 {
+        self.attached_document_types.get(trade_id)
+    }
+    
+    // Get the platform fee a new trade would be charged at a price
+    #[payable(false)]
+    pub fn quote_fee_basis_points(&self, price: U256) -> U256 {
+        // Start from the base fee
+        let mut fee_basis_points = self.fee_basis_points.get();
+        
+        // Apply the highest volume tier the price reaches, if it is cheaper
+        let tier_count = self.fee_tier_count.get();
+        let mut i = U256::ZERO;
+        while i < tier_count {
+            let tier = self.fee_tiers.get(i);
+            if price < tier.min_amount {
+                break;
+            }
+            if tier.fee_basis_points < fee_basis_points {
+                fee_basis_points = tier.fee_basis_points;
+            }
+            i = i + U256::from(1u32);
+        }
+        
+        fee_basis_points
+    }
+    
+    // Get a volume fee tier by index
+    #[payable(false)]
+    pub fn get_fee_tier(&self, tier_id: U256) -> Result<FeeTier, Vec<u8>> {
+        // Ensure tier ID is valid
+        if tier_id >= self.fee_tier_count.get() {
+            return Err(b"Fee tier not found".to_vec());
+        }
+        
+        Ok(self.fee_tiers.get(tier_id))
+    }
+    
+    // Get number of volume fee tiers
+    #[payable(false)]
+    pub fn get_fee_tier_count(&self) -> U256 {
+        self.fee_tier_count.get()
+    }
+    
+    // Check whether an exporter's signed-offer nonce has been used or cancelled
+    #[payable(false)]
+    pub fn is_offer_nonce_used(&self, exporter: Address, nonce: U256) -> bool {
+        self.used_offer_nonces.get(exporter).get(nonce)
+    }
+    
+    // Get the EIP-712 domain separator for signed trade offers
+    #[payable(false)]
+    pub fn domain_separator(&self) -> [u8; 32] {
+        let mut encoded = Vec::new();
+        encoded.extend_from_slice(&keccak(EIP712_DOMAIN_TYPE));
+        encoded.extend_from_slice(&keccak(EIP712_DOMAIN_NAME));
+        encoded.extend_from_slice(&keccak(EIP712_DOMAIN_VERSION));
+        encoded.extend_from_slice(&U256::from(stylus_sdk::block::chainid()).to_be_bytes::<32>());
+        encoded.extend_from_slice(&address_word(stylus_sdk::contract::address()));
+        keccak(&encoded)
+    }
+    
+    // Get the EIP-712 digest an exporter signs for a trade offer
+    #[payable(false)]
+    pub fn get_offer_digest(
+        &self,
+        exporter: Address,
+        importer: Address,
+        price: U256,
+        description_hash: [u8; 32],
+        expiry_duration: U256,
+        incoterm: u8,
+        required_documents: u32,
+        nonce: U256,
+        deadline: U256,
+    ) -> [u8; 32] {
+        // Hash the typed struct
+        let mut encoded = Vec::new();
+        encoded.extend_from_slice(&keccak(TRADE_OFFER_TYPE));
+        encoded.extend_from_slice(&address_word(exporter));
+        encoded.extend_from_slice(&address_word(importer));
+        encoded.extend_from_slice(&price.to_be_bytes::<32>());
+        encoded.extend_from_slice(&description_hash);
+        encoded.extend_from_slice(&expiry_duration.to_be_bytes::<32>());
+        encoded.extend_from_slice(&U256::from(incoterm).to_be_bytes::<32>());
+        encoded.extend_from_slice(&U256::from(required_documents).to_be_bytes::<32>());
+        encoded.extend_from_slice(&nonce.to_be_bytes::<32>());
+        encoded.extend_from_slice(&deadline.to_be_bytes::<32>());
+        let struct_hash = keccak(&encoded);
+        
+        // Combine with the domain separator
+        let mut message = Vec::new();
+        message.extend_from_slice(b"\x19\x01");
+        message.extend_from_slice(&self.domain_separator());
+        message.extend_from_slice(&struct_hash);
+        keccak(&message)
+    }
+    
+    // Get the total payment required to fund a trade (price plus any insurance premium)
+    #[payable(false)]
+    pub fn get_funding_amount(&self, trade_id: U256) -> Result<U256, Vec<u8>> {
+        let trade = self.get_trade(trade_id)?;
+        Ok(trade.price + self.premium_for(&trade))
+    }
+    
+    // Get insurance pool balance and capital reserved for active coverage
+    #[payable(false)]
+    pub fn get_insurance_pool(&self) -> (U256, U256) {
+        (self.insurance_pool_balance.get(), self.insurance_reserved.get())
+    }
+    
+    // Get an underwriter's pool shares and the total shares issued
+    #[payable(false)]
+    pub fn get_underwriter_shares(&self, underwriter: Address) -> (U256, U256) {
+        (self.underwriter_shares.get(underwriter), self.total_underwriter_shares.get())
+    }
+    
+    // Get the pending partial settlement offer for a trade
+    #[payable(false)]
+    pub fn get_pending_partial_settlement(&self, trade_id: U256) -> Result<PartialSettlementOffer, Vec<u8>> {
+        let offer = self.pending_partial_settlements.get(trade_id);
+        
+        // Ensure offer exists (check if proposer is non-zero)
+        if offer.proposed_by == Address::ZERO {
+            return Err(b"No pending partial settlement".to_vec());
+        }
+        
+        Ok(offer)
+    }
+    
+    // Get the settlement record of a resolved dispute or partial settlement
+    #[payable(false)]
+    pub fn get_settlement(&self, trade_id: U256) -> Result<Settlement, Vec<u8>> {
+        let settlement = self.settlements.get(trade_id);
+        
+        // Ensure settlement exists (check if resolved_at is non-zero)
+        if settlement.resolved_at == U256::ZERO {
+            return Err(b"Settlement not found".to_vec());
+        }
+        
+        Ok(settlement)
+    }
+    
+    // Get number of trades created by an exporter
+    #[payable(false)]
+    pub fn get_exporter_trade_count(&self, exporter: Address) -> U256 {
+        self.exporter_trade_counts.get(exporter)
+    }
+    
+    // Get number of trades addressed to an importer
+    #[payable(false)]
+    pub fn get_importer_trade_count(&self, importer: Address) -> U256 {
+        self.importer_trade_counts.get(importer)
+    }
+    
+    // Get a page of trade IDs for an exporter, oldest first
+    #[payable(false)]
+    pub fn get_exporter_trades(&self, exporter: Address, offset: U256, limit: U256) -> Vec<U256> {
+        self.list_party_trades(true, exporter, None, offset, limit).0
+    }
+    
+    // Get a page of trade IDs for an importer, oldest first
+    #[payable(false)]
+    pub fn get_importer_trades(&self, importer: Address, offset: U256, limit: U256) -> Vec<U256> {
+        self.list_party_trades(false, importer, None, offset, limit).0
+    }
+    
+    // Get a page of an exporter's trade IDs in a given status, scanning from an index position;
+    // also returns the position to resume from
+    #[payable(false)]
+    pub fn get_exporter_trades_by_status(
+        &self,
+        exporter: Address,
+        status: u8,
+        offset: U256,
+        limit: U256,
+    ) -> (Vec<U256>, U256) {
+        self.list_party_trades(true, exporter, Some(status), offset, limit)
+    }
+    
+    // Get a page of an importer's trade IDs in a given status, scanning from an index position;
+    // also returns the position to resume from
+    #[payable(false)]
+    pub fn get_importer_trades_by_status(
+        &self,
+        importer: Address,
+        status: u8,
+        offset: U256,
+        limit: U256,
+    ) -> (Vec<U256>, U256) {
+        self.list_party_trades(false, importer, Some(status), offset, limit)
+    }
+    
+    // Get trade history statistics for an address (as exporter or importer)
+    #[payable(false)]
+    pub fn get_party_stats(&self, party: Address) -> PartyStats {
+        self.party_stats.get(party)
+    }
+    
+    // Get average funding-to-delivery time in seconds for an address
+    #[payable(false)]
+    pub fn get_average_delivery_time(&self, party: Address) -> U256 {
+        let stats = self.party_stats.get(party);
+        
+        // Avoid division by zero for parties with no deliveries
+        if stats.delivered_trades == U256::ZERO {
+            return U256::ZERO;
+        }
+        
+        stats.total_delivery_time / stats.delivered_trades
+    }
+    
+    // Check whether every required document type has been attached
+    #[payable(false)]
+    pub fn has_required_documents(&self, trade_id: U256) -> Result<bool, Vec<u8>> {
+        let trade = self.get_trade(trade_id)?;
+        let attached = self.attached_document_types.get(trade_id);
+        Ok(attached & trade.required_documents == trade.required_documents)
+    }
+}
+
+// Internal helpers
+impl TradeEscrow {
+    // Create and index a new trade for an exporter
+    fn open_trade(
+        &mut self,
+        exporter: Address,
+        importer: Address,
+        price: U256,
+        description_hash: [u8; 32],
+        expiry_duration: U256,
+        incoterm: u8,
+        required_documents: u32,
+    ) -> Result<U256, Vec<u8>> {
+        // Ensure not sending to self
+        if exporter == importer {
+            return Err(b"Cannot trade with self".to_vec());
+        }
+        
+        // Ensure a price has been agreed
+        if price == U256::ZERO {
+            return Err(b"Zero price".to_vec());
+        }
+        
+        // Validate Incoterm
+        if incoterm > MAX_INCOTERM {
+            return Err(b"Invalid incoterm".to_vec());
+        }
+        
+        // Get current trade ID and increment counter
+        let trade_id = self.trade_counter.get();
+        self.trade_counter.set(trade_id + U256::from(1u32));
+        
+        // Calculate expiration timestamp
+        let current_time = block_timestamp();
+        let expires_at = current_time + expiry_duration;
+        
+        // Snapshot the fee and refund grace period so later admin changes don't alter agreed terms
+        let fee_basis_points = self.quote_fee_basis_points(price);
+        let refund_grace_period = self.refund_grace_period.get();
+        
+        // Create new trade
+        let trade = Trade {
+            exporter,
+            importer,
+            price,
+            amount: U256::ZERO,
+            fee_basis_points,
+            status: 0, // Created
+            description_hash,
+            created_at: current_time,
+            expires_at,
+            refund_grace_period,
+            funded_at: U256::ZERO,
+            incoterm,
+            required_documents,
+            guarantor: Address::ZERO,
+            guarantor_approved: false,
+            insurance_requested: false,
+            insured: false,
+            insurance_premium: U256::ZERO,
+        };
+        
+        // Save trade in storage
+        self.trades.insert(trade_id, trade);
+        
+        // Index trade under both parties
+        let exporter_count = self.exporter_trade_counts.get(exporter);
+        self.exporter_trades.get(exporter).insert(exporter_count, trade_id);
+        self.exporter_trade_counts.insert(exporter, exporter_count + U256::from(1u32));
+        
+        let importer_count = self.importer_trade_counts.get(importer);
+        self.importer_trades.get(importer).insert(importer_count, trade_id);
+        self.importer_trade_counts.insert(importer, importer_count + U256::from(1u32));
+        
+        // Return the trade ID
+        Ok(trade_id)
+    }
+    
+    // Move a created trade into escrow with the given payment
+    fn fund(&mut self, trade_id: U256, funder: Address, amount: U256) -> Result<(), Vec<u8>> {
+        // Get trade from storage
+        let mut trade = self.get_trade(trade_id)?;
+        
+        // Ensure funder is the funding party (guarantor if nominated, otherwise importer)
+        if funder != Self::funding_party(&trade) {
+            return Err(b"Not the funding party".to_vec());
+        }
+        
+        // Ensure trade is in created status
+        if trade.status != 0 {
+            return Err(b"Invalid trade status".to_vec());
+        }
+        
+        // Ensure not expired
+        let current_time = block_timestamp();
+        if current_time > trade.expires_at {
+            return Err(b"Trade expired".to_vec());
+        }
+        
+        // Ensure payment matches the agreed price plus any insurance premium
+        let premium = self.premium_for(&trade);
+        if amount != trade.price + premium {
+            return Err(b"Incorrect payment amount".to_vec());
+        }
+        
+        // Reserve full coverage from the pool and add the premium to it
+        if trade.insured {
+            let reserved = self.insurance_reserved.get();
+            if self.insurance_pool_balance.get() - reserved < trade.price {
+                return Err(b"Insufficient insurance capacity".to_vec());
+            }
+            self.insurance_reserved.set(reserved + trade.price);
+            self.insurance_pool_balance.set(self.insurance_pool_balance.get() + premium);
+            trade.insurance_premium = premium;
+        }
+        
+        // Set trade amount and update status
+        trade.amount = trade.price;
+        trade.status = 1; // Funded
+        trade.funded_at = current_time;
+        
+        // Update trade in storage
+        self.trades.insert(trade_id, trade);
+        
+        Ok(())
+    }
+    
+    // Insurance premium due at funding (zero for uninsured trades)
+    fn premium_for(&self, trade: &Trade) -> U256 {
+        if trade.insured {
+            (trade.price * self.insurance_premium_basis_points.get()) / U256::from(10000u32)
+        } else {
+            U256::ZERO
+        }
+    }
+    
+    // Release the pool capital reserved for an insured trade's coverage
+    fn release_coverage(&mut self, trade: &Trade) {
+        if trade.insured {
+            let reserved = self.insurance_reserved.get();
+            self.insurance_reserved.set(reserved - trade.amount);
+        }
+    }
+    
+    // Recover the address that signed a digest, rejecting malleable signatures
+    fn recover_signer(digest: [u8; 32], v: u8, r: [u8; 32], s: [u8; 32]) -> Result<Address, Vec<u8>> {
+        if (v != 27 && v != 28) || s > SECP256K1_HALF_ORDER {
+            return Err(b"Invalid signature".to_vec());
+        }
+        
+        // ecrecover input: digest, v (as a 32-byte word), r, s
+        let mut input = [0u8; 128];
+        input[..32].copy_from_slice(&digest);
+        input[63] = v;
+        input[64..96].copy_from_slice(&r);
+        input[96..].copy_from_slice(&s);
+        
+        let output = static_call(Call::new(), ECRECOVER, &input)
+            .map_err(|_| b"Signature recovery failed".to_vec())?;
+        
+        // Precompile returns no data for an invalid signature
+        if output.len() != 32 {
+            return Err(b"Invalid signature".to_vec());
+        }
+        
+        let signer = Address::from_slice(&output[12..]);
+        if signer == Address::ZERO {
+            return Err(b"Invalid signature".to_vec());
+        }
+        
+        Ok(signer)
+    }
+    
+    // Party that funds the trade and receives refunds (guarantor if nominated, otherwise importer)
+    fn funding_party(trade: &Trade) -> Address {
+        if trade.guarantor != Address::ZERO {
+            trade.guarantor
+        } else {
+            trade.importer
+        }
+    }
+    
+    // Collect a page of a party's trade IDs from an index position, optionally filtered by status;
+    // returns the page and the index position after the last entry scanned
+    fn list_party_trades(
+        &self,
+        as_exporter: bool,
+        party: Address,
+        status: Option<u8>,
+        offset: U256,
+        limit: U256,
+    ) -> (Vec<U256>, U256) {
+        let (index, count) = if as_exporter {
+            (self.exporter_trades.get(party), self.exporter_trade_counts.get(party))
+        } else {
+            (self.importer_trades.get(party), self.importer_trade_counts.get(party))
+        };
+        
+        // Cap page size
+        let max_page_size = U256::from(MAX_PAGE_SIZE);
+        let limit = if limit > max_page_size { max_page_size } else { limit };
+        
+        // Cap entries scanned, so a filter matching few trades can't exhaust gas
+        let scan_end = offset.saturating_add(U256::from(MAX_SCAN_SIZE));
+        let end = if scan_end < count { scan_end } else { count };
+        
+        let mut trade_ids = Vec::new();
+        let mut i = offset;
+        while i < end && U256::from(trade_ids.len()) < limit {
+            let trade_id = index.get(i);
+            i = i + U256::from(1u32);
+            
+            // Skip trades not in the requested status
+            if let Some(status) = status {
+                if self.trades.get(trade_id).status != status {
+                    continue;
+                }
+            }
+            
+            trade_ids.push(trade_id);
+        }
+        
+        (trade_ids, i)
+    }
+    
+    // Record a trade completed by delivery confirmation
+    fn record_delivery(&mut self, party: Address, volume: U256, delivery_time: U256) {
+        let mut stats = self.party_stats.get(party);
+        stats.completed_trades = stats.completed_trades + U256::from(1u32);
+        stats.total_volume = stats.total_volume + volume;
+        stats.delivered_trades = stats.delivered_trades + U256::from(1u32);
+        stats.total_delivery_time = stats.total_delivery_time + delivery_time;
+        self.party_stats.insert(party, stats);
+    }
+    
+    // Record a trade refunded after expiry
+    fn record_refund(&mut self, party: Address) {
+        let mut stats = self.party_stats.get(party);
+        stats.refunded_trades = stats.refunded_trades + U256::from(1u32);
+        self.party_stats.insert(party, stats);
+    }
+    
+    // Record a trade entering dispute
+    fn record_dispute(&mut self, party: Address) {
+        let mut stats = self.party_stats.get(party);
+        stats.disputed_trades = stats.disputed_trades + U256::from(1u32);
+        self.party_stats.insert(party, stats);
+    }
+    
+    // Record a trade settled by dispute resolution
+    fn record_resolution(&mut self, party: Address, volume: U256, won: bool, lost: bool) {
+        let mut stats = self.party_stats.get(party);
+        stats.completed_trades = stats.completed_trades + U256::from(1u32);
+        stats.total_volume = stats.total_volume + volume;
+        if won {
+            stats.disputes_won = stats.disputes_won + U256::from(1u32);
+        }
+        if lost {
+            stats.disputes_lost = stats.disputes_lost + U256::from(1u32);
+        }
+        self.party_stats.insert(party, stats);
+    }
+    
+    // Credit settled funds to an address, to be pulled with withdraw
+    fn credit(&mut self, account: Address, amount: U256) {
+        if amount > U256::ZERO {
+            let balance = self.balances.get(account);
+            self.balances.insert(account, balance + amount);
+        }
+    }
+}
+
+// Helper function to hash bytes with keccak256
+fn keccak(data: &[u8]) -> [u8; 32] {
+    stylus_sdk::crypto::keccak(data).0
+}
+
+// Helper function to left-pad an address to a 32-byte ABI word
+fn address_word(address: Address) -> [u8; 32] {
+    let mut word = [0u8; 32];
+    word[12..].copy_from_slice(address.as_slice());
+    word
 }
 
 // Helper function to get current block timestamp
@@ -6,10 +6,90 @@
 #![no_std]
 use stylus_sdk::{
     alloy_primitives::{Address, U256},
+    call::{static_call, Call},
     msg, prelude::*,
     stylus_proc::*,
 };
 
+// Ballot types (0=plurality, 1=ranked-choice with instant-runoff tallying,
+// 2=approval, where voters select any number of candidates, 3=yes/no/abstain proposition)
+const BALLOT_PLURALITY: u8 = 0;
+const BALLOT_RANKED: u8 = 1;
+const BALLOT_APPROVAL: u8 = 2;
+const BALLOT_PROPOSITION: u8 = 3;
+
+// Proposition choices, cast as candidate IDs
+const CHOICE_YES: u32 = 0;
+const CHOICE_NO: u32 = 1;
+const CHOICE_ABSTAIN: u32 = 2;
+
+// Proposition thresholds (0=simple majority of yes/no votes, 1=two-thirds supermajority)
+const THRESHOLD_SIMPLE: u8 = 0;
+const THRESHOLD_TWO_THIRDS: u8 = 1;
+
+// Proposition outcomes (0=not a proposition, 1=passed, 2=failed, 3=quorum not met)
+const OUTCOME_NONE: u8 = 0;
+const OUTCOME_PASSED: u8 = 1;
+const OUTCOME_FAILED: u8 = 2;
+const OUTCOME_QUORUM_NOT_MET: u8 = 3;
+
+// Election statuses reported by get_election_status
+const STATUS_SCHEDULED: u8 = 0;
+const STATUS_ACTIVE: u8 = 1;
+const STATUS_ENDED: u8 = 2;
+const STATUS_FINALIZED: u8 = 3;
+const STATUS_CANCELLED: u8 = 4;
+
+// Lifecycle changes recorded against an election
+const CHANGE_CANCEL: u8 = 0;
+const CHANGE_POSTPONE: u8 = 1;
+const CHANGE_EXTEND: u8 = 2;
+const CHANGE_METADATA: u8 = 3;
+
+// Maximum candidates in a ranked-choice election (bounds runoff gas)
+const MAX_RANKED_CANDIDATES: u32 = 32;
+
+// Tie-break policies applied when finalization finds a tie for first place
+const TIE_NO_WINNER: u8 = 0;
+const TIE_RUNOFF: u8 = 1;
+const TIE_LOT: u8 = 2;
+
+// Eligibility modes (0=all registered voters, 1=explicit voter list, 2=voters tagged with a district,
+// 3=voters proven against a Merkle root, without individual registration)
+const ELIGIBILITY_ALL: u8 = 0;
+const ELIGIBILITY_LIST: u8 = 1;
+const ELIGIBILITY_DISTRICT: u8 = 2;
+const ELIGIBILITY_MERKLE: u8 = 3;
+
+// Candidate statuses (0=on the ballot, 1=nomination pending, 2=withdrawn, 3=nomination rejected)
+const CANDIDATE_ACTIVE: u8 = 0;
+const CANDIDATE_PENDING: u8 = 1;
+const CANDIDATE_WITHDRAWN: u8 = 2;
+const CANDIDATE_REJECTED: u8 = 3;
+
+// Delegation scope for proxies that apply to every election (election IDs never reach it)
+const GLOBAL_DELEGATION: U256 = U256::MAX;
+
+// Maximum current direct delegators listed under one delegate per scope (keeps each list a bounded
+// read; chains of up to MAX_DELEGATION_DEPTH links can still carry more votes in total)
+const MAX_DELEGATORS: u32 = 64;
+
+// Maximum length of a delegation chain
+const MAX_DELEGATION_DEPTH: u32 = 16;
+
+// Maximum open elections in which one voter can have a per-election proxy at a time
+const MAX_ELECTION_PROXIES: u32 = 16;
+
+// Weighting modes (0=one voter one vote, 1=ERC-20 voting power at the snapshot, 2=admin-assigned weights)
+const WEIGHT_EQUAL: u8 = 0;
+const WEIGHT_TOKEN: u8 = 1;
+const WEIGHT_ASSIGNED: u8 = 2;
+
+// getPastVotes(address,uint256) selector (ERC20Votes / ERC-5805 tokens)
+const GET_PAST_VOTES_SELECTOR: [u8; 4] = [0x3a, 0x46, 0xb1, 0xa8];
+// ERC-6372 CLOCK_MODE() selector; tokens without it count in block numbers
+const CLOCK_MODE_SELECTOR: [u8; 4] = [0x4b, 0xf5, 0xd7, 0xe9];
+
 // Storage for voting system
 #[derive(StorageField)]
 struct VotingStorage {
@@ -31,6 +111,129 @@ struct VotingStorage {
     // Maps voter address to their ID hash (for verification)
     #[selector(0x5)]
     voter_registry: StorageMap<Address, [u8; 32]>,
+    // Maps election ID to ranked ballots (ballot index -> rank -> candidate ID)
+    #[selector(0x6)]
+    ranked_ballots: StorageMap<U256, StorageMap<U256, StorageMap<U256, U256>>>,
+    // Maps election ID to number of rankings on each ranked ballot
+    #[selector(0x7)]
+    ranked_ballot_lengths: StorageMap<U256, StorageMap<U256, U256>>,
+    // Maps election ID to number of ranked ballots cast
+    #[selector(0x8)]
+    ranked_ballot_counts: StorageMap<U256, U256>,
+    // Maps election ID to its tie record (set when finalization finds a tie)
+    #[selector(0x9)]
+    ties: StorageMap<U256, TieRecord>,
+    // Maps election ID to the candidates tied for first place
+    #[selector(0xa)]
+    tied_candidates: StorageMap<U256, StorageMap<U256, U256>>,
+    // Maps runoff election ID to the original candidate ID of each runoff candidate
+    #[selector(0xb)]
+    runoff_candidates: StorageMap<U256, StorageMap<U256, U256>>,
+    // Maps election ID to each voter's sealed ballot commitment
+    #[selector(0xc)]
+    commitments: StorageMap<U256, StorageMap<Address, [u8; 32]>>,
+    // Maps election ID to whether each voter has revealed their ballot
+    #[selector(0xd)]
+    revealed: StorageMap<U256, StorageMap<Address, bool>>,
+    // Maps election ID to number of sealed ballots committed
+    #[selector(0xe)]
+    commit_counts: StorageMap<U256, U256>,
+    // Maps election ID to number of sealed ballots revealed
+    #[selector(0xf)]
+    reveal_counts: StorageMap<U256, U256>,
+    // Maps voter address to their constituency/district tag (e.g., "Gasabo")
+    #[selector(0x10)]
+    voter_districts: StorageMap<Address, [u8; 32]>,
+    // Maps election ID to its explicit list of eligible voters
+    #[selector(0x11)]
+    eligible_voters: StorageMap<U256, StorageMap<Address, bool>>,
+    // Maps election ID to voters who have proven membership in its voter Merkle root
+    #[selector(0x12)]
+    merkle_verified: StorageMap<U256, StorageMap<Address, bool>>,
+    // Maps election ID to its candidate registry (candidate ID -> Candidate)
+    #[selector(0x13)]
+    candidates: StorageMap<U256, StorageMap<U256, Candidate>>,
+    // Maps election ID and candidate ID to voters who endorsed the nomination
+    #[selector(0x14)]
+    endorsements: StorageMap<U256, StorageMap<U256, StorageMap<Address, bool>>>,
+    // Maps delegation scope (election ID or GLOBAL_DELEGATION) and delegator to their proxy
+    #[selector(0x15)]
+    delegates: StorageMap<U256, StorageMap<Address, Address>>,
+    // Maps delegation scope and delegate to everyone who has delegated to them (index -> delegator)
+    #[selector(0x16)]
+    delegators: StorageMap<U256, StorageMap<Address, StorageMap<U256, Address>>>,
+    // Maps delegation scope and delegate to the length of their delegator list
+    #[selector(0x17)]
+    delegator_counts: StorageMap<U256, StorageMap<Address, U256>>,
+    // Maps delegation scope, delegate and delegator to the delegator's list index plus one (0 = not listed)
+    #[selector(0x18)]
+    delegator_indexes: StorageMap<U256, StorageMap<Address, StorageMap<Address, U256>>>,
+    // Maps election ID to the weight of each ranked ballot (voter plus delegators)
+    #[selector(0x19)]
+    ranked_ballot_weights: StorageMap<U256, StorageMap<U256, U256>>,
+    // Maps election ID to admin-assigned voter weights (weighting mode 2)
+    #[selector(0x1a)]
+    voter_weights: StorageMap<U256, StorageMap<Address, U256>>,
+    // Maps election ID to the candidates drawn by lot, in draw order
+    #[selector(0x1b)]
+    lot_winners: StorageMap<U256, StorageMap<U256, U256>>,
+    // Maps election ID to the total weight of approval ballots cast
+    #[selector(0x1c)]
+    approval_ballot_weights: StorageMap<U256, U256>,
+    // Maps election ID to its lifecycle changes (index -> LifecycleChange)
+    #[selector(0x1d)]
+    lifecycle_changes: StorageMap<U256, StorageMap<U256, LifecycleChange>>,
+    // Maps election ID to number of lifecycle changes recorded
+    #[selector(0x1e)]
+    lifecycle_change_counts: StorageMap<U256, U256>,
+    // Maps election ID to its official result summary (set at finalization)
+    #[selector(0x1f)]
+    results: StorageMap<U256, ResultSummary>,
+    // Maps election ID to its final tally in result order (index -> CandidateTally)
+    #[selector(0x20)]
+    result_tallies: StorageMap<U256, StorageMap<U256, CandidateTally>>,
+    // Maps election ID to candidates elected outright, in result order
+    #[selector(0x21)]
+    result_winners: StorageMap<U256, StorageMap<U256, U256>>,
+    // Number of registered voters
+    #[selector(0x22)]
+    registered_voter_count: StorageU256,
+    // Maps election ID to number of voters who took part (directly or through a proxy)
+    #[selector(0x23)]
+    participant_counts: StorageMap<U256, U256>,
+    // Maps election ID and first-choice candidate to their ranked ballots (index -> ballot ID)
+    #[selector(0x24)]
+    first_preference_ballots: StorageMap<U256, StorageMap<U256, StorageMap<U256, U256>>>,
+    // Maps election ID and first-choice candidate to the number of ranked ballots in their bucket
+    #[selector(0x25)]
+    first_preference_counts: StorageMap<U256, StorageMap<U256, U256>>,
+    // Maps election ID to the lot randomness commitment made at creation (keccak of the randomness)
+    #[selector(0x26)]
+    lot_commitments: StorageMap<U256, [u8; 32]>,
+    // Maps election ID to each self-nominee's candidate ID plus one (0 = never nominated)
+    #[selector(0x27)]
+    nominations: StorageMap<U256, StorageMap<Address, U256>>,
+    // Maps voter to the elections in which they have appointed a per-election proxy (index -> election ID)
+    #[selector(0x28)]
+    election_proxies: StorageMap<Address, StorageMap<U256, U256>>,
+    // Maps election ID to the weight each sealed ballot carries, fixed when it is committed
+    #[selector(0x29)]
+    committed_weights: StorageMap<U256, StorageMap<Address, U256>>,
+    // Maps election ID to the number of voters on its explicit eligibility list
+    #[selector(0x2a)]
+    eligible_voter_counts: StorageMap<U256, U256>,
+    // Maps district to the number of voters tagged with it
+    #[selector(0x2b)]
+    district_voter_counts: StorageMap<[u8; 32], U256>,
+    // Maps voter to the length of their election proxy list
+    #[selector(0x2c)]
+    election_proxy_counts: StorageMap<Address, U256>,
+    // Maps district to the number of unfinalized elections restricted to it
+    #[selector(0x2d)]
+    district_election_counts: StorageMap<[u8; 32], U256>,
+    // Maps district to the earliest start among those elections, when its voter tags freeze
+    #[selector(0x2e)]
+    district_frozen_from: StorageMap<[u8; 32], U256>,
 }
 
 // Election struct to store election data
@@ -46,8 +249,105 @@ struct Election {
     end_time: U256,
     // Whether results have been finalized
     finalized: bool,
+    // Whether the admin cancelled the election before voting opened
+    cancelled: bool,
     // Hash of election metadata (IPFS hash to candidate info)
     metadata_hash: [u8; 32],
+    // Ballot type (0=plurality, 1=ranked-choice, 2=approval, 3=proposition)
+    ballot_type: u8,
+    // Number of seats to fill (1 for single-winner elections)
+    seats: U256,
+    // Tie-break policy (0=no winner, 1=runoff election, 2=admin-drawn lot)
+    tie_break_policy: u8,
+    // End of the reveal phase for commit-reveal ballots (0 for open ballots)
+    reveal_end_time: U256,
+    // Eligibility mode (0=all registered, 1=explicit list, 2=district)
+    eligibility: u8,
+    // District voters must be tagged with (eligibility mode 2)
+    district: [u8; 32],
+    // Merkle root of eligible (address, id_hash) leaves (eligibility mode 3)
+    voter_merkle_root: [u8; 32],
+    // End of the nomination phase (0 if nominations were never opened)
+    nomination_end_time: U256,
+    // Endorsements that put a self-nomination on the ballot (0 = admin approval only)
+    endorsement_threshold: U256,
+    // Weighting mode (0=equal, 1=ERC-20 voting power, 2=admin-assigned)
+    weighting: u8,
+    // ERC20Votes token whose past voting power weighs ballots (weighting mode 1)
+    weight_token: Address,
+    // Time at which weights are captured (0 for equal weighting)
+    snapshot_time: U256,
+    // Election whose assigned weights apply (itself, or the original election for a runoff)
+    weight_source: U256,
+    // Minimum vote weight, abstentions included, for a proposition to be decided
+    quorum: U256,
+    // Proposition threshold (0=simple majority, 1=two-thirds)
+    threshold: u8,
+    // Election whose eligibility list and Merkle proofs apply (itself, or the original election for a runoff)
+    eligibility_source: U256,
+    // Number of leaves in the voter Merkle tree, as published with the root
+    merkle_voter_count: U256,
+}
+
+// Candidate registry entry (candidates counted at creation start active with empty details)
+#[derive(PartialEq, Clone)]
+struct Candidate {
+    // Candidate name (e.g., "Jean Uwimana")
+    name: [u8; 32],
+    // Party or affiliation (empty for independents)
+    party: [u8; 32],
+    // Hash of candidate metadata (IPFS hash to manifesto, photo, etc.)
+    metadata_hash: [u8; 32],
+    // Status (0=active, 1=nomination pending, 2=withdrawn, 3=rejected)
+    status: u8,
+    // Self-nominated candidate's address (zero for admin-listed candidates)
+    nominee: Address,
+    // Number of voter endorsements received
+    endorsement_count: U256,
+}
+
+// Admin change to an election's schedule or details, with the reason given
+#[derive(PartialEq, Clone)]
+struct LifecycleChange {
+    // Change type (0=cancel, 1=postpone, 2=extend, 3=metadata correction)
+    action: u8,
+    // Reason for the change (e.g., hash of the published notice)
+    reason: [u8; 32],
+    // When the change was made
+    changed_at: U256,
+    // Voting period before the change
+    previous_start_time: U256,
+    previous_end_time: U256,
+    // Metadata hash before the change
+    previous_metadata_hash: [u8; 32],
+}
+
+// Votes counted for one candidate
+#[derive(PartialEq, Clone)]
+struct CandidateTally {
+    // Candidate ID
+    candidate_id: U256,
+    // Votes counted (final-round votes for ranked-choice)
+    votes: U256,
+}
+
+// Tie at the seat cutoff found at finalization and how it was broken
+#[derive(PartialEq, Clone)]
+struct TieRecord {
+    // Tie-break policy in force for the election
+    policy: u8,
+    // Number of candidates tied for the last seats (first place in single-seat elections)
+    tied_count: U256,
+    // Seats left for the tied candidates to fill
+    open_seats: U256,
+    // Whether the tie has been broken (by lot or by runoff creation)
+    resolved: bool,
+    // First candidate drawn by lot (policy 2; see get_lot_winners for multi-seat draws)
+    lot_winner: U256,
+    // Randomness the admin supplied for the lot draw
+    lot_randomness: [u8; 32],
+    // Runoff election created among the tied candidates (policy 1)
+    runoff_election_id: U256,
 }
 
 // Result struct for election results
@@ -55,12 +355,58 @@ struct Election {
 struct ElectionResult {
     // Election ID
     election_id: U256,
-    // Winning candidate ID
+    // Whether every seat was filled (false on a tie at the seat cutoff)
+    has_winner: bool,
+    // Highest-placed candidate ID (lowest tied ID on a tie for first place)
     winning_candidate: U256,
     // Number of votes for winning candidate
     winning_votes: U256,
     // Total votes cast
     total_votes: U256,
+    // Number of counting rounds (1 for plurality and approval)
+    rounds: U256,
+    // Number of seats to fill
+    seats: U256,
+    // Candidates elected outright, most votes first
+    winners: Vec<U256>,
+    // Candidates tied for the remaining seats (empty when there is no tie)
+    tied_candidates: Vec<U256>,
+    // All candidate counts, most votes first (ties ordered by candidate ID)
+    tally: Vec<CandidateTally>,
+    // Sealed ballots committed but never revealed (not counted)
+    unrevealed_commits: U256,
+    // Proposition outcome (0=not a proposition, 1=passed, 2=failed, 3=quorum not met)
+    outcome: u8,
+    // Voters who took part, directly or through a proxy (sealed ballots count when committed)
+    participants: U256,
+    // Voters eligible for the election at finalization (see electorate_size)
+    electorate: U256,
+    // Participants as basis points of the electorate (0 when the electorate is empty)
+    turnout_basis_points: U256,
+}
+
+// Official result persisted at finalization (lists are stored separately)
+#[derive(PartialEq, Clone)]
+struct ResultSummary {
+    // Whether the result has been recorded
+    recorded: bool,
+    // Fields copied from ElectionResult
+    has_winner: bool,
+    winning_candidate: U256,
+    winning_votes: U256,
+    total_votes: U256,
+    rounds: U256,
+    seats: U256,
+    unrevealed_commits: U256,
+    outcome: u8,
+    participants: U256,
+    electorate: U256,
+    turnout_basis_points: U256,
+    // Lengths of the stored winner and tally lists
+    winner_count: U256,
+    tally_count: U256,
+    // When the election was finalized
+    finalized_at: U256,
 }
 
 // Main contract implementation
@@ -98,98 +444,106 @@ impl VotingSystem {
         
         // Register voter with their ID hash
         self.voter_registry.insert(voter, id_hash);
+        self.registered_voter_count.set(self.registered_voter_count.get() + U256::from(1u32));
         
         Ok(())
     }
     
-    // Create a new election
+    // Tag a registered voter with their constituency/district
     #[payable(false)]
-    pub fn create_election(
-        &mut self,
-        name: [u8; 32],
-        candidate_count: U256,
-        start_time: U256,
-        end_time: U256,
-        metadata_hash: [u8; 32],
-    ) -> Result<U256, Vec<u8>> {
-        // Only admin can create elections
+    pub fn set_voter_district(&mut self, voter: Address, district: [u8; 32]) -> Result<(), Vec<u8>> {
+        // Only admin can tag voters
         if msg::sender() != self.admin.get() {
             return Err(b"Not admin".to_vec());
         }
         
-        // Validate inputs
-        if candidate_count == U256::ZERO {
-            return Err(b"No candidates".to_vec());
+        // Ensure voter is registered
+        if self.voter_registry.get(voter) == [0; 32] {
+            return Err(b"Voter not registered".to_vec());
         }
         
-        if end_time <= start_time {
-            return Err(b"Invalid time period".to_vec());
+        // Tags can't change while an election restricted to either district is under way,
+        // so the electorate stays fixed from the start of voting to finalization
+        let previous = self.voter_districts.get(voter);
+        if self.is_district_frozen(previous) || self.is_district_frozen(district) {
+            return Err(b"District election under way".to_vec());
         }
         
-        // Get current election ID and increment counter
-        let election_id = self.election_counter.get();
-        self.election_counter.set(election_id + U256::from(1u32));
-        
-        // Create new election
-        let election = Election {
-            name,
-            candidate_count,
-            start_time,
-            end_time,
-            finalized: false,
-            metadata_hash,
-        };
+        // Move the voter between district counts
+        if previous != [0; 32] {
+            let count = self.district_voter_counts.get(previous);
+            self.district_voter_counts.insert(previous, count - U256::from(1u32));
+        }
+        if district != [0; 32] {
+            let count = self.district_voter_counts.get(district);
+            self.district_voter_counts.insert(district, count + U256::from(1u32));
+        }
         
-        // Save election in storage
-        self.elections.insert(election_id, election);
+        self.voter_districts.insert(voter, district);
         
-        // Return the election ID
-        Ok(election_id)
+        Ok(())
     }
     
-    // Cast a vote in an election
+    // Restrict who may vote in an election before voting opens
     #[payable(false)]
-    pub fn cast_vote(&mut self, election_id: U256, candidate_id: U256) -> Result<(), Vec<u8>> {
-        let voter = msg::sender();
-        
-        // Ensure voter is registered
-        if self.voter_registry.get(voter) == [0; 32] {
-            return Err(b"Voter not registered".to_vec());
+    pub fn set_election_eligibility(
+        &mut self,
+        election_id: U256,
+        eligibility: u8,
+        district: [u8; 32],
+    ) -> Result<(), Vec<u8>> {
+        // Only admin can set eligibility
+        if msg::sender() != self.admin.get() {
+            return Err(b"Not admin".to_vec());
         }
         
         // Get election from storage
-        let election = self.get_election(election_id)?;
+        let mut election = self.get_election(election_id)?;
         
-        // Ensure election is active
-        let current_time = block_timestamp();
-        if current_time < election.start_time || current_time > election.end_time {
-            return Err(b"Election not active".to_vec());
+        // Ensure voting hasn't started
+        if block_timestamp() >= election.start_time {
+            return Err(b"Election already started".to_vec());
         }
         
-        // Ensure candidate ID is valid
-        if candidate_id >= election.candidate_count {
-            return Err(b"Invalid candidate".to_vec());
+        // Runoffs keep the electorate of the election they settle
+        if election.eligibility_source != election_id {
+            return Err(b"Runoff electorate fixed".to_vec());
         }
         
-        // Ensure voter hasn't already voted
-        if self.ballots.get(election_id).get(voter) {
-            return Err(b"Already voted".to_vec());
+        // Validate mode (Merkle roots are bound via set_voter_merkle_root)
+        if eligibility > ELIGIBILITY_DISTRICT {
+            return Err(b"Invalid eligibility mode".to_vec());
         }
         
-        // Record vote
-        let current_votes = self.votes.get(election_id).get(candidate_id);
-        self.votes.get(election_id).insert(candidate_id, current_votes + U256::from(1u32));
+        if eligibility == ELIGIBILITY_DISTRICT && district == [0; 32] {
+            return Err(b"No district".to_vec());
+        }
         
-        // Mark voter as having voted
-        self.ballots.get(election_id).insert(voter, true);
+        // Track which district the election's electorate is drawn from
+        if election.eligibility == ELIGIBILITY_DISTRICT {
+            self.release_district(election.district);
+        }
+        if eligibility == ELIGIBILITY_DISTRICT {
+            self.track_district(district, election.start_time);
+        }
+        
+        // Update election
+        election.eligibility = eligibility;
+        election.district = district;
+        self.elections.insert(election_id, election);
         
         Ok(())
     }
     
-    // Finalize election results
+    // Bind an election to a Merkle root of eligible voters, published in one transaction
     #[payable(false)]
-    pub fn finalize_election(&mut self, election_id: U256) -> Result<ElectionResult, Vec<u8>> {
-        // Only admin can finalize elections
+    pub fn set_voter_merkle_root(
+        &mut self,
+        election_id: U256,
+        voter_merkle_root: [u8; 32],
+        voter_count: U256,
+    ) -> Result<(), Vec<u8>> {
+        // Only admin can set eligibility
         if msg::sender() != self.admin.get() {
             return Err(b"Not admin".to_vec());
         }
@@ -197,93 +551,2425 @@ impl VotingSystem {
         // Get election from storage
         let mut election = self.get_election(election_id)?;
         
-        // Ensure election has ended
-        let current_time = block_timestamp();
-        if current_time <= election.end_time {
-            return Err(b"Election still active".to_vec());
+        // Ensure voting hasn't started
+        if block_timestamp() >= election.start_time {
+            return Err(b"Election already started".to_vec());
         }
         
-        // Ensure election isn't already finalized
-        if election.finalized {
-            return Err(b"Already finalized".to_vec());
+        // Runoffs keep the electorate of the election they settle
+        if election.eligibility_source != election_id {
+            return Err(b"Runoff electorate fixed".to_vec());
         }
         
-        // Find winning candidate
-        let mut winning_candidate = U256::ZERO;
-        let mut winning_votes = U256::ZERO;
-        let mut total_votes = U256::ZERO;
+        if voter_merkle_root == [0; 32] || voter_count == U256::ZERO {
+            return Err(b"Empty Merkle root".to_vec());
+        }
         
-        for i in 0..election.candidate_count.as_usize() {
-            let candidate_id = U256::from(i as u32);
-            let votes = self.votes.get(election_id).get(candidate_id);
-            
-            total_votes = total_votes + votes;
-            
-            if votes > winning_votes {
-                winning_votes = votes;
-                winning_candidate = candidate_id;
-            }
+        if election.eligibility == ELIGIBILITY_DISTRICT {
+            self.release_district(election.district);
         }
         
-        // Mark election as finalized
-        election.finalized = true;
+        // Update election
+        election.eligibility = ELIGIBILITY_MERKLE;
+        election.voter_merkle_root = voter_merkle_root;
+        election.merkle_voter_count = voter_count;
         self.elections.insert(election_id, election);
         
-        // Return election results
-        Ok(ElectionResult {
-            election_id,
-            winning_candidate,
-            winning_votes,
-            total_votes,
-        })
+        Ok(())
     }
     
-    // Check if an address has voted in an election
+    // Add or remove voters on an election's explicit eligibility list before voting opens
     #[payable(false)]
-    pub fn has_voted(&self, election_id: U256, voter: Address) -> bool {
-        self.ballots.get(election_id).get(voter)
+    pub fn set_eligible_voters(
+        &mut self,
+        election_id: U256,
+        voters: Vec<Address>,
+        eligible: bool,
+    ) -> Result<(), Vec<u8>> {
+        // Only admin can set eligibility
+        if msg::sender() != self.admin.get() {
+            return Err(b"Not admin".to_vec());
+        }
+        
+        // Get election from storage
+        let election = self.get_election(election_id)?;
+        
+        // Ensure voting hasn't started
+        if block_timestamp() >= election.start_time {
+            return Err(b"Election already started".to_vec());
+        }
+        
+        // Runoffs keep the electorate of the election they settle
+        if election.eligibility_source != election_id {
+            return Err(b"Runoff electorate fixed".to_vec());
+        }
+        
+        // Count only voters whose listing changes, so repeats don't skew turnout
+        let mut count = self.eligible_voter_counts.get(election_id);
+        for voter in voters.iter() {
+            if self.eligible_voters.get(election_id).get(*voter) == eligible {
+                continue;
+            }
+            self.eligible_voters.get(election_id).insert(*voter, eligible);
+            count = if eligible { count + U256::from(1u32) } else { count - U256::from(1u32) };
+        }
+        self.eligible_voter_counts.insert(election_id, count);
+        
+        Ok(())
     }
     
-    // Get vote count for a candidate
+    // Assign voting weights for an election before its snapshot time (weighting mode 2)
     #[payable(false)]
-    pub fn get_vote_count(&self, election_id: U256, candidate_id: U256) -> U256 {
-        self.votes.get(election_id).get(candidate_id)
+    pub fn set_voter_weights(
+        &mut self,
+        election_id: U256,
+        voters: Vec<Address>,
+        weights: Vec<U256>,
+    ) -> Result<(), Vec<u8>> {
+        // Only admin can assign weights
+        if msg::sender() != self.admin.get() {
+            return Err(b"Not admin".to_vec());
+        }
+        
+        // Get election from storage
+        let election = self.get_election(election_id)?;
+        
+        if election.weighting != WEIGHT_ASSIGNED {
+            return Err(b"Weights not assigned".to_vec());
+        }
+        
+        // Weights are frozen from the snapshot onwards
+        if block_timestamp() >= election.snapshot_time {
+            return Err(b"Snapshot taken".to_vec());
+        }
+        
+        if voters.len() != weights.len() {
+            return Err(b"Length mismatch".to_vec());
+        }
+        
+        for (voter, weight) in voters.iter().zip(weights.iter()) {
+            self.voter_weights.get(election_id).insert(*voter, *weight);
+        }
+        
+        Ok(())
     }
     
-    // Get election details by ID
+    // Create a new election
     #[payable(false)]
-    pub fn get_election(&self, election_id: U256) -> Result<Election, Vec<u8>> {
-        let election = self.elections.get(election_id);
+    pub fn create_election(
+        &mut self,
+        name: [u8; 32],
+        candidate_count: U256,
+        start_time: U256,
+        end_time: U256,
+        metadata_hash: [u8; 32],
+        ballot_type: u8,
+        tie_break_policy: u8,
+        lot_commitment: [u8; 32],
+        reveal_end_time: U256,
+        weighting: u8,
+        weight_token: Address,
+        snapshot_time: U256,
+        seats: U256,
+    ) -> Result<U256, Vec<u8>> {
+        // Only admin can create elections
+        if msg::sender() != self.admin.get() {
+            return Err(b"Not admin".to_vec());
+        }
         
-        // Ensure election exists
-        if election.start_time == U256::ZERO && election.end_time == U256::ZERO {
-            return Err(b"Election not found".to_vec());
+        // Propositions need a quorum and threshold
+        if ballot_type == BALLOT_PROPOSITION {
+            return Err(b"Use create_referendum".to_vec());
         }
         
-        Ok(election)
+        self.open_election(
+            name,
+            candidate_count,
+            start_time,
+            end_time,
+            metadata_hash,
+            ballot_type,
+            tie_break_policy,
+            lot_commitment,
+            reveal_end_time,
+            weighting,
+            weight_token,
+            snapshot_time,
+            seats,
+        )
     }
     
-    // Check if a voter is registered
+    // Create a referendum on a yes/no/abstain proposition (choices 0=yes, 1=no, 2=abstain)
     #[payable(false)]
-    pub fn is_registered(&self, voter: Address) -> bool {
-        self.voter_registry.get(voter) != [0; 32]
+    pub fn create_referendum(
+        &mut self,
+        name: [u8; 32],
+        start_time: U256,
+        end_time: U256,
+        metadata_hash: [u8; 32],
+        reveal_end_time: U256,
+        weighting: u8,
+        weight_token: Address,
+        snapshot_time: U256,
+        quorum: U256,
+        threshold: u8,
+    ) -> Result<U256, Vec<u8>> {
+        // Only admin can create elections
+        if msg::sender() != self.admin.get() {
+            return Err(b"Not admin".to_vec());
+        }
+        
+        if threshold > THRESHOLD_TWO_THIRDS {
+            return Err(b"Invalid threshold".to_vec());
+        }
+        
+        // Choices are fixed candidates; a level vote fails rather than ties
+        let election_id = self.open_election(
+            name,
+            U256::from(3u32),
+            start_time,
+            end_time,
+            metadata_hash,
+            BALLOT_PROPOSITION,
+            TIE_NO_WINNER,
+            [0; 32],
+            reveal_end_time,
+            weighting,
+            weight_token,
+            snapshot_time,
+            U256::from(1u32),
+        )?;
+        
+        // Save quorum and threshold
+        let mut election = self.get_election(election_id)?;
+        election.quorum = quorum;
+        election.threshold = threshold;
+        self.elections.insert(election_id, election);
+        
+        // Return the election ID
+        Ok(election_id)
     }
     
-    // Get voter ID hash
+    // Create a runoff election among the candidates tied in a finalized election
     #[payable(false)]
-    pub fn get_voter_id_hash(&self, voter: Address) -> Result<[u8; 32], Vec<u8>> {
-        let id_hash = self.voter_registry.get(voter);
+    pub fn create_runoff_election(
+        &mut self,
+        election_id: U256,
+        start_time: U256,
+        end_time: U256,
+        tie_break_policy: u8,
+        lot_commitment: [u8; 32],
+    ) -> Result<U256, Vec<u8>> {
+        // Only admin can create elections
+        if msg::sender() != self.admin.get() {
+            return Err(b"Not admin".to_vec());
+        }
         
-        // Ensure voter is registered
-        if id_hash == [0; 32] {
-            return Err(b"Voter not registered".to_vec());
+        // Ensure the election ended in an unresolved tie under the runoff policy
+        let election = self.get_election(election_id)?;
+        let mut tie = self.get_tie(election_id)?;
+        if tie.policy != TIE_RUNOFF {
+            return Err(b"Tie policy is not runoff".to_vec());
+        }
+        if tie.resolved {
+            return Err(b"Tie already resolved".to_vec());
         }
         
-        Ok(id_hash)
+        // Secret ballots stay secret in the runoff, with the same reveal window length
+        let reveal_end_time = if election.reveal_end_time == U256::ZERO {
+            U256::ZERO
+        } else {
+            end_time + (election.reveal_end_time - election.end_time)
+        };
+        
+        // Runoff is a plurality vote among the tied candidates for the seats still open,
+        // weighted as the original
+        let runoff_id = self.open_election(
+            election.name,
+            tie.tied_count,
+            start_time,
+            end_time,
+            election.metadata_hash,
+            BALLOT_PLURALITY,
+            tie_break_policy,
+            lot_commitment,
+            reveal_end_time,
+            election.weighting,
+            election.weight_token,
+            election.snapshot_time,
+            tie.open_seats,
+        )?;
+        
+        // Assigned weights and the electorate carry over from the original election
+        let mut runoff = self.get_election(runoff_id)?;
+        runoff.weight_source = election.weight_source;
+        runoff.eligibility = election.eligibility;
+        runoff.district = election.district;
+        runoff.voter_merkle_root = election.voter_merkle_root;
+        runoff.eligibility_source = election.eligibility_source;
+        runoff.merkle_voter_count = election.merkle_voter_count;
+        if runoff.eligibility == ELIGIBILITY_DISTRICT {
+            self.track_district(runoff.district, runoff.start_time);
+        }
+        self.elections.insert(runoff_id, runoff);
+        
+        // Map runoff candidate IDs back to the original candidates
+        let mut i = U256::ZERO;
+        while i < tie.tied_count {
+            let original = self.tied_candidates.get(election_id).get(i);
+            self.runoff_candidates.get(runoff_id).insert(i, original);
+            i = i + U256::from(1u32);
+        }
+        
+        // Record the runoff against the tie
+        tie.resolved = true;
+        tie.runoff_election_id = runoff_id;
+        self.ties.insert(election_id, tie);
+        
+        // Return the runoff election ID
+        Ok(runoff_id)
     }
-}
-
-// Helper function to get current block timestamp
-fn block_timestamp() -> U256 {
-    U256::from(stylus_sdk::block::timestamp())
+    
+    // Cancel an election before voting opens
+    #[payable(false)]
+    pub fn cancel_election(&mut self, election_id: U256, reason: [u8; 32]) -> Result<(), Vec<u8>> {
+        // Only admin can cancel elections
+        if msg::sender() != self.admin.get() {
+            return Err(b"Not admin".to_vec());
+        }
+        
+        // Ensure election is still scheduled
+        let mut election = self.get_election(election_id)?;
+        if self.election_status(&election) != STATUS_SCHEDULED {
+            return Err(b"Election not scheduled".to_vec());
+        }
+        
+        self.record_change(election_id, &election, CHANGE_CANCEL, reason);
+        
+        if election.eligibility == ELIGIBILITY_DISTRICT {
+            self.release_district(election.district);
+        }
+        
+        // Update election
+        election.cancelled = true;
+        self.elections.insert(election_id, election);
+        
+        Ok(())
+    }
+    
+    // Move a scheduled election's voting period later (secret ballots keep their reveal window length)
+    #[payable(false)]
+    pub fn postpone_election(
+        &mut self,
+        election_id: U256,
+        new_start_time: U256,
+        new_end_time: U256,
+        reason: [u8; 32],
+    ) -> Result<(), Vec<u8>> {
+        // Only admin can postpone elections
+        if msg::sender() != self.admin.get() {
+            return Err(b"Not admin".to_vec());
+        }
+        
+        // Ensure election is still scheduled
+        let mut election = self.get_election(election_id)?;
+        if self.election_status(&election) != STATUS_SCHEDULED {
+            return Err(b"Election not scheduled".to_vec());
+        }
+        
+        // A later start keeps nomination and snapshot times before voting opens
+        if new_start_time <= election.start_time || new_end_time <= new_start_time {
+            return Err(b"Invalid time period".to_vec());
+        }
+        
+        self.record_change(election_id, &election, CHANGE_POSTPONE, reason);
+        
+        // Update election
+        if election.reveal_end_time != U256::ZERO {
+            election.reveal_end_time = new_end_time + (election.reveal_end_time - election.end_time);
+        }
+        election.start_time = new_start_time;
+        election.end_time = new_end_time;
+        self.elections.insert(election_id, election);
+        
+        Ok(())
+    }
+    
+    // Extend voting before it closes (secret ballots keep their reveal window length)
+    #[payable(false)]
+    pub fn extend_election(&mut self, election_id: U256, new_end_time: U256, reason: [u8; 32]) -> Result<(), Vec<u8>> {
+        // Only admin can extend elections
+        if msg::sender() != self.admin.get() {
+            return Err(b"Not admin".to_vec());
+        }
+        
+        // Ensure voting hasn't closed
+        let mut election = self.get_election(election_id)?;
+        let status = self.election_status(&election);
+        if status != STATUS_SCHEDULED && status != STATUS_ACTIVE {
+            return Err(b"Voting closed".to_vec());
+        }
+        
+        if new_end_time <= election.end_time {
+            return Err(b"Invalid time period".to_vec());
+        }
+        
+        self.record_change(election_id, &election, CHANGE_EXTEND, reason);
+        
+        // Update election
+        if election.reveal_end_time != U256::ZERO {
+            election.reveal_end_time = election.reveal_end_time + (new_end_time - election.end_time);
+        }
+        election.end_time = new_end_time;
+        self.elections.insert(election_id, election);
+        
+        Ok(())
+    }
+    
+    // Correct an election's metadata hash before voting opens
+    #[payable(false)]
+    pub fn update_election_metadata(
+        &mut self,
+        election_id: U256,
+        metadata_hash: [u8; 32],
+        reason: [u8; 32],
+    ) -> Result<(), Vec<u8>> {
+        // Only admin can update elections
+        if msg::sender() != self.admin.get() {
+            return Err(b"Not admin".to_vec());
+        }
+        
+        // Ensure election is still scheduled
+        let mut election = self.get_election(election_id)?;
+        if self.election_status(&election) != STATUS_SCHEDULED {
+            return Err(b"Election not scheduled".to_vec());
+        }
+        
+        self.record_change(election_id, &election, CHANGE_METADATA, reason);
+        
+        // Update election
+        election.metadata_hash = metadata_hash;
+        self.elections.insert(election_id, election);
+        
+        Ok(())
+    }
+    
+    // Add a named candidate to the ballot before voting opens
+    #[payable(false)]
+    pub fn add_candidate(
+        &mut self,
+        election_id: U256,
+        name: [u8; 32],
+        party: [u8; 32],
+        metadata_hash: [u8; 32],
+    ) -> Result<U256, Vec<u8>> {
+        // Only admin can add candidates directly
+        if msg::sender() != self.admin.get() {
+            return Err(b"Not admin".to_vec());
+        }
+        
+        self.register_candidate(election_id, name, party, metadata_hash, Address::ZERO, CANDIDATE_ACTIVE)
+    }
+    
+    // Name a candidate counted at creation, before voting opens
+    #[payable(false)]
+    pub fn set_candidate_details(
+        &mut self,
+        election_id: U256,
+        candidate_id: U256,
+        name: [u8; 32],
+        party: [u8; 32],
+        metadata_hash: [u8; 32],
+    ) -> Result<(), Vec<u8>> {
+        // Only admin can edit candidates
+        if msg::sender() != self.admin.get() {
+            return Err(b"Not admin".to_vec());
+        }
+        
+        // Ensure voting hasn't started
+        let election = self.get_election(election_id)?;
+        if block_timestamp() >= election.start_time {
+            return Err(b"Election already started".to_vec());
+        }
+        
+        if name == [0; 32] {
+            return Err(b"Empty name".to_vec());
+        }
+        
+        // Update candidate
+        let mut candidate = self.get_candidate(election_id, candidate_id)?;
+        candidate.name = name;
+        candidate.party = party;
+        candidate.metadata_hash = metadata_hash;
+        self.candidates.get(election_id).insert(candidate_id, candidate);
+        
+        Ok(())
+    }
+    
+    // Open a nomination phase that closes no later than the start of voting
+    #[payable(false)]
+    pub fn open_nominations(
+        &mut self,
+        election_id: U256,
+        nomination_end_time: U256,
+        endorsement_threshold: U256,
+    ) -> Result<(), Vec<u8>> {
+        // Only admin can open nominations
+        if msg::sender() != self.admin.get() {
+            return Err(b"Not admin".to_vec());
+        }
+        
+        // Get election from storage
+        let mut election = self.get_election(election_id)?;
+        
+        // Nominations must close by the time voting opens
+        if nomination_end_time <= block_timestamp() || nomination_end_time > election.start_time {
+            return Err(b"Invalid nomination period".to_vec());
+        }
+        
+        // Update election
+        election.nomination_end_time = nomination_end_time;
+        election.endorsement_threshold = endorsement_threshold;
+        self.elections.insert(election_id, election);
+        
+        Ok(())
+    }
+    
+    // Nominate yourself as a candidate during the nomination phase
+    #[payable(false)]
+    pub fn nominate(
+        &mut self,
+        election_id: U256,
+        name: [u8; 32],
+        party: [u8; 32],
+        metadata_hash: [u8; 32],
+    ) -> Result<U256, Vec<u8>> {
+        let nominee = msg::sender();
+        
+        // Ensure nomination phase is open
+        let election = self.get_election(election_id)?;
+        if block_timestamp() > election.nomination_end_time {
+            return Err(b"Nominations closed".to_vec());
+        }
+        
+        // Ensure nominee is registered
+        if self.voter_registry.get(nominee) == [0; 32] {
+            return Err(b"Nominee not registered".to_vec());
+        }
+        
+        // A withdrawn or rejected nominee may try again under their original candidate ID,
+        // so repeated nominations can't fill the ballot
+        let nomination = self.nominations.get(election_id).get(nominee);
+        if nomination != U256::ZERO {
+            let candidate_id = nomination - U256::from(1u32);
+            let mut candidate = self.get_candidate(election_id, candidate_id)?;
+            if candidate.status == CANDIDATE_ACTIVE || candidate.status == CANDIDATE_PENDING {
+                return Err(b"Already nominated".to_vec());
+            }
+            
+            if name == [0; 32] {
+                return Err(b"Empty name".to_vec());
+            }
+            
+            // Update candidate; endorsements gathered earlier still count
+            candidate.name = name;
+            candidate.party = party;
+            candidate.metadata_hash = metadata_hash;
+            candidate.status = if election.endorsement_threshold != U256::ZERO
+                && candidate.endorsement_count >= election.endorsement_threshold
+            {
+                CANDIDATE_ACTIVE
+            } else {
+                CANDIDATE_PENDING
+            };
+            self.candidates.get(election_id).insert(candidate_id, candidate);
+            
+            return Ok(candidate_id);
+        }
+        
+        let candidate_id = self.register_candidate(election_id, name, party, metadata_hash, nominee, CANDIDATE_PENDING)?;
+        self.nominations.get(election_id).insert(nominee, candidate_id + U256::from(1u32));
+        
+        Ok(candidate_id)
+    }
+    
+    // Endorse a pending nomination; it goes on the ballot once the threshold is reached
+    #[payable(false)]
+    pub fn endorse_candidate(&mut self, election_id: U256, candidate_id: U256) -> Result<(), Vec<u8>> {
+        let endorser = msg::sender();
+        
+        // Get election and candidate from storage
+        let election = self.get_election(election_id)?;
+        let mut candidate = self.get_candidate(election_id, candidate_id)?;
+        
+        // Ensure nomination phase is open and the nomination is pending
+        if block_timestamp() > election.nomination_end_time {
+            return Err(b"Nominations closed".to_vec());
+        }
+        
+        if candidate.status != CANDIDATE_PENDING {
+            return Err(b"Nomination not pending".to_vec());
+        }
+        
+        // Nominees can't endorse themselves
+        if endorser == candidate.nominee {
+            return Err(b"Cannot endorse own nomination".to_vec());
+        }
+        
+        // Ensure endorser is registered (or proven against the voter root) and eligible
+        self.authorize_voter(&election, endorser)?;
+        
+        // Ensure endorser hasn't already endorsed this candidate
+        if self.endorsements.get(election_id).get(candidate_id).get(endorser) {
+            return Err(b"Already endorsed".to_vec());
+        }
+        self.endorsements.get(election_id).get(candidate_id).insert(endorser, true);
+        
+        // Record endorsement and put the candidate on the ballot at the threshold
+        candidate.endorsement_count = candidate.endorsement_count + U256::from(1u32);
+        if election.endorsement_threshold != U256::ZERO
+            && candidate.endorsement_count >= election.endorsement_threshold
+        {
+            candidate.status = CANDIDATE_ACTIVE;
+        }
+        self.candidates.get(election_id).insert(candidate_id, candidate);
+        
+        Ok(())
+    }
+    
+    // Approve or reject a pending nomination before voting opens
+    #[payable(false)]
+    pub fn review_nomination(&mut self, election_id: U256, candidate_id: U256, approved: bool) -> Result<(), Vec<u8>> {
+        // Only admin can review nominations
+        if msg::sender() != self.admin.get() {
+            return Err(b"Not admin".to_vec());
+        }
+        
+        // Ensure voting hasn't started
+        let election = self.get_election(election_id)?;
+        if block_timestamp() >= election.start_time {
+            return Err(b"Election already started".to_vec());
+        }
+        
+        // Ensure nomination is pending
+        let mut candidate = self.get_candidate(election_id, candidate_id)?;
+        if candidate.status != CANDIDATE_PENDING {
+            return Err(b"Nomination not pending".to_vec());
+        }
+        
+        // Update candidate
+        candidate.status = if approved { CANDIDATE_ACTIVE } else { CANDIDATE_REJECTED };
+        self.candidates.get(election_id).insert(candidate_id, candidate);
+        
+        Ok(())
+    }
+    
+    // Withdraw a candidacy before voting opens (the nominee, or admin for any candidate)
+    #[payable(false)]
+    pub fn withdraw_candidacy(&mut self, election_id: U256, candidate_id: U256) -> Result<(), Vec<u8>> {
+        let sender = msg::sender();
+        
+        // Ensure voting hasn't started
+        let election = self.get_election(election_id)?;
+        if block_timestamp() >= election.start_time {
+            return Err(b"Election already started".to_vec());
+        }
+        
+        // Proposition choices are fixed
+        if election.ballot_type == BALLOT_PROPOSITION {
+            return Err(b"Not a candidate election".to_vec());
+        }
+        
+        // Ensure sender is the nominee or admin
+        let mut candidate = self.get_candidate(election_id, candidate_id)?;
+        let is_nominee = candidate.nominee != Address::ZERO && sender == candidate.nominee;
+        if !is_nominee && sender != self.admin.get() {
+            return Err(b"Not nominee".to_vec());
+        }
+        
+        // Ensure candidate is still standing
+        if candidate.status != CANDIDATE_ACTIVE && candidate.status != CANDIDATE_PENDING {
+            return Err(b"Candidate not standing".to_vec());
+        }
+        
+        // Update candidate
+        candidate.status = CANDIDATE_WITHDRAWN;
+        self.candidates.get(election_id).insert(candidate_id, candidate);
+        
+        Ok(())
+    }
+    
+    // Appoint a registered voter as proxy for one election, before the sender votes
+    #[payable(false)]
+    pub fn delegate_vote(&mut self, election_id: U256, delegate: Address) -> Result<(), Vec<u8>> {
+        let delegator = msg::sender();
+        
+        // Get election from storage
+        let election = self.get_election(election_id)?;
+        
+        // Ensure voting hasn't ended
+        if block_timestamp() > election.end_time {
+            return Err(b"Election ended".to_vec());
+        }
+        
+        // Ensure both parties may vote in this election, so the proxy can carry the vote
+        self.authorize_voter(&election, delegator)?;
+        if self.authorize_voter(&election, delegate).is_err() {
+            return Err(b"Delegate not eligible".to_vec());
+        }
+        
+        // Ensure delegator hasn't already voted
+        if self.ballots.get(election_id).get(delegator) {
+            return Err(b"Already voted".to_vec());
+        }
+        
+        // Global proxies can't appoint per-election proxies, so global chains never pass through an
+        // election override (a global delegation into one could otherwise close a loop in that election)
+        if self.has_global_delegators(delegator) {
+            return Err(b"Delegator is a global proxy".to_vec());
+        }
+        
+        // Make room in the delegator's election proxy list by dropping lapsed entries
+        let replacing = self.delegates.get(election_id).get(delegator) != Address::ZERO;
+        if !replacing {
+            self.prune_election_proxies(delegator);
+            if self.election_proxy_counts.get(delegator) >= U256::from(MAX_ELECTION_PROXIES) {
+                return Err(b"Too many election proxies".to_vec());
+            }
+        }
+        
+        // Ensure the chain doesn't end at someone who has already voted
+        let final_delegate = self.set_delegate(election_id, delegator, delegate)?;
+        if self.ballots.get(election_id).get(final_delegate) {
+            return Err(b"Delegate already voted".to_vec());
+        }
+        
+        // Record the election so global delegations can check the proxy while it is live
+        if !replacing {
+            let count = self.election_proxy_counts.get(delegator);
+            self.election_proxies.get(delegator).insert(count, election_id);
+            self.election_proxy_counts.insert(delegator, count + U256::from(1u32));
+        }
+        
+        Ok(())
+    }
+    
+    // Appoint a registered voter as proxy for every election the sender doesn't vote in
+    #[payable(false)]
+    pub fn delegate_globally(&mut self, delegate: Address) -> Result<(), Vec<u8>> {
+        self.set_delegate(GLOBAL_DELEGATION, msg::sender(), delegate)?;
+        Ok(())
+    }
+    
+    // Revoke a proxy for one election, before it has voted on the sender's behalf
+    #[payable(false)]
+    pub fn revoke_delegation(&mut self, election_id: U256) -> Result<(), Vec<u8>> {
+        let delegator = msg::sender();
+        
+        // Ensure there is a delegation to revoke
+        if self.delegates.get(election_id).get(delegator) == Address::ZERO {
+            return Err(b"No delegation".to_vec());
+        }
+        
+        // Ensure the vote hasn't already been cast by the proxy
+        if self.ballots.get(election_id).get(delegator) {
+            return Err(b"Already voted".to_vec());
+        }
+        
+        let delegate = self.delegates.get(election_id).get(delegator);
+        self.unlist_delegator(election_id, delegate, delegator);
+        self.delegates.get(election_id).insert(delegator, Address::ZERO);
+        
+        Ok(())
+    }
+    
+    // Revoke a global proxy (ballots it already cast on the sender's behalf still stand)
+    #[payable(false)]
+    pub fn revoke_global_delegation(&mut self) -> Result<(), Vec<u8>> {
+        let delegator = msg::sender();
+        
+        // Ensure there is a delegation to revoke
+        if self.delegates.get(GLOBAL_DELEGATION).get(delegator) == Address::ZERO {
+            return Err(b"No delegation".to_vec());
+        }
+        
+        let delegate = self.delegates.get(GLOBAL_DELEGATION).get(delegator);
+        self.unlist_delegator(GLOBAL_DELEGATION, delegate, delegator);
+        self.delegates.get(GLOBAL_DELEGATION).insert(delegator, Address::ZERO);
+        
+        Ok(())
+    }
+    
+    // Cast a vote in an election
+    #[payable(false)]
+    pub fn cast_vote(&mut self, election_id: U256, candidate_id: U256) -> Result<(), Vec<u8>> {
+        let voter = msg::sender();
+        
+        // Get election from storage
+        let election = self.get_election(election_id)?;
+        
+        // Ensure election is active
+        let current_time = block_timestamp();
+        if election.cancelled || current_time < election.start_time || current_time > election.end_time {
+            return Err(b"Election not active".to_vec());
+        }
+        
+        // Ranked and approval elections take full ballots via their own functions
+        if election.ballot_type != BALLOT_PLURALITY && election.ballot_type != BALLOT_PROPOSITION {
+            return Err(b"Not a plurality election".to_vec());
+        }
+        
+        // Secret-ballot elections take sealed ballots via commit_vote
+        if election.reveal_end_time != U256::ZERO {
+            return Err(b"Secret ballot election".to_vec());
+        }
+        
+        // Ensure candidate ID is valid and on the ballot
+        if candidate_id >= election.candidate_count || !self.is_candidate_active(election_id, candidate_id) {
+            return Err(b"Invalid candidate".to_vec());
+        }
+        
+        // Ensure voter is registered (or proven against the voter root) and eligible
+        self.authorize_voter(&election, voter)?;
+        
+        // Ensure voter hasn't already voted or handed their vote to a proxy
+        if self.ballots.get(election_id).get(voter) {
+            return Err(b"Already voted".to_vec());
+        }
+        
+        if self.effective_delegate(election_id, voter) != Address::ZERO {
+            return Err(b"Vote delegated".to_vec());
+        }
+        
+        // Mark voter as having voted, then count the ballot for the voter and their delegators
+        self.mark_voted(election_id, voter);
+        let weight = self.claim_ballot_weight(&election, election_id, voter)?;
+        
+        // Record vote
+        let current_votes = self.votes.get(election_id).get(candidate_id);
+        self.votes.get(election_id).insert(candidate_id, current_votes + weight);
+        
+        Ok(())
+    }
+    
+    // Prove the sender's (address, id_hash) leaf is in an election's voter Merkle root
+    #[payable(false)]
+    pub fn prove_voter(&mut self, election_id: U256, id_hash: [u8; 32], proof: Vec<[u8; 32]>) -> Result<(), Vec<u8>> {
+        let voter = msg::sender();
+        
+        // Get election from storage
+        let election = self.get_election(election_id)?;
+        
+        // Ensure election uses a voter Merkle root
+        if election.eligibility != ELIGIBILITY_MERKLE {
+            return Err(b"No voter Merkle root".to_vec());
+        }
+        
+        // Verify proof
+        if !self.verify_voter_proof(election_id, voter, id_hash, proof)? {
+            return Err(b"Invalid Merkle proof".to_vec());
+        }
+        
+        // Record voter as verified for this election (and any runoff it settles)
+        self.merkle_verified.get(election.eligibility_source).insert(voter, true);
+        
+        Ok(())
+    }
+    
+    // Prove membership in the voter Merkle root and cast a vote in one transaction
+    #[payable(false)]
+    pub fn cast_vote_with_proof(
+        &mut self,
+        election_id: U256,
+        candidate_id: U256,
+        id_hash: [u8; 32],
+        proof: Vec<[u8; 32]>,
+    ) -> Result<(), Vec<u8>> {
+        self.prove_voter(election_id, id_hash, proof)?;
+        self.cast_vote(election_id, candidate_id)
+    }
+    
+    // Commit a sealed ballot in a secret-ballot election (see compute_commitment)
+    #[payable(false)]
+    pub fn commit_vote(&mut self, election_id: U256, commitment: [u8; 32]) -> Result<(), Vec<u8>> {
+        let voter = msg::sender();
+        
+        // Get election from storage
+        let election = self.get_election(election_id)?;
+        
+        // Ensure election is active
+        let current_time = block_timestamp();
+        if election.cancelled || current_time < election.start_time || current_time > election.end_time {
+            return Err(b"Election not active".to_vec());
+        }
+        
+        // Ensure election uses secret ballots
+        if election.reveal_end_time == U256::ZERO {
+            return Err(b"Not a secret ballot election".to_vec());
+        }
+        
+        // Ensure voter is registered (or proven against the voter root) and eligible
+        self.authorize_voter(&election, voter)?;
+        
+        // Ensure voter hasn't already voted or handed their vote to a proxy
+        if self.ballots.get(election_id).get(voter) {
+            return Err(b"Already voted".to_vec());
+        }
+        
+        if self.effective_delegate(election_id, voter) != Address::ZERO {
+            return Err(b"Vote delegated".to_vec());
+        }
+        
+        if commitment == [0; 32] {
+            return Err(b"Empty commitment".to_vec());
+        }
+        
+        // Store commitment
+        self.commitments.get(election_id).insert(voter, commitment);
+        let commit_count = self.commit_counts.get(election_id);
+        self.commit_counts.insert(election_id, commit_count + U256::from(1u32));
+        
+        // Mark voter as having voted, then fix the ballot's weight for the voter and their delegators
+        // while voting is open, so delegations made after polls close can't add to it at reveal
+        self.mark_voted(election_id, voter);
+        let weight = self.claim_ballot_weight(&election, election_id, voter)?;
+        self.committed_weights.get(election_id).insert(voter, weight);
+        
+        Ok(())
+    }
+    
+    // Reveal a sealed ballot after voting ends, counting the vote
+    #[payable(false)]
+    pub fn reveal_vote(&mut self, election_id: U256, candidate_id: U256, salt: [u8; 32]) -> Result<(), Vec<u8>> {
+        let voter = msg::sender();
+        
+        // Get election from storage
+        let election = self.get_election(election_id)?;
+        
+        // Ensure election uses secret ballots
+        if election.reveal_end_time == U256::ZERO {
+            return Err(b"Not a secret ballot election".to_vec());
+        }
+        
+        // Ensure reveal phase is open
+        let current_time = block_timestamp();
+        if current_time <= election.end_time || current_time > election.reveal_end_time {
+            return Err(b"Reveal phase not active".to_vec());
+        }
+        
+        // Ensure voter committed and hasn't revealed yet
+        let commitment = self.commitments.get(election_id).get(voter);
+        if commitment == [0; 32] {
+            return Err(b"No commitment".to_vec());
+        }
+        
+        if self.revealed.get(election_id).get(voter) {
+            return Err(b"Already revealed".to_vec());
+        }
+        
+        // Ensure the revealed choice matches the commitment
+        if self.compute_commitment(election_id, voter, candidate_id, salt) != commitment {
+            return Err(b"Commitment mismatch".to_vec());
+        }
+        
+        // Ensure candidate ID is valid and on the ballot
+        if candidate_id >= election.candidate_count || !self.is_candidate_active(election_id, candidate_id) {
+            return Err(b"Invalid candidate".to_vec());
+        }
+        
+        // Record vote with the weight fixed at commit
+        let weight = self.committed_weights.get(election_id).get(voter);
+        let current_votes = self.votes.get(election_id).get(candidate_id);
+        self.votes.get(election_id).insert(candidate_id, current_votes + weight);
+        
+        // Mark ballot as revealed
+        self.revealed.get(election_id).insert(voter, true);
+        let reveal_count = self.reveal_counts.get(election_id);
+        self.reveal_counts.insert(election_id, reveal_count + U256::from(1u32));
+        
+        Ok(())
+    }
+    
+    // Cast a ranked ballot (most preferred first) in a ranked-choice election
+    #[payable(false)]
+    pub fn cast_ranked_vote(&mut self, election_id: U256, rankings: Vec<U256>) -> Result<(), Vec<u8>> {
+        let voter = msg::sender();
+        
+        // Get election from storage
+        let election = self.get_election(election_id)?;
+        
+        // Ensure election is active
+        let current_time = block_timestamp();
+        if election.cancelled || current_time < election.start_time || current_time > election.end_time {
+            return Err(b"Election not active".to_vec());
+        }
+        
+        // Ensure election uses ranked ballots
+        if election.ballot_type != BALLOT_RANKED {
+            return Err(b"Not a ranked election".to_vec());
+        }
+        
+        // Ensure voter is registered (or proven against the voter root) and eligible
+        self.authorize_voter(&election, voter)?;
+        
+        // Ensure voter hasn't already voted or handed their vote to a proxy
+        if self.ballots.get(election_id).get(voter) {
+            return Err(b"Already voted".to_vec());
+        }
+        
+        if self.effective_delegate(election_id, voter) != Address::ZERO {
+            return Err(b"Vote delegated".to_vec());
+        }
+        
+        // Validate rankings: at least one, each a valid candidate ranked once
+        let candidate_count = election.candidate_count.as_usize();
+        if rankings.is_empty() || rankings.len() > candidate_count {
+            return Err(b"Invalid rankings".to_vec());
+        }
+        
+        let mut seen = [false; MAX_RANKED_CANDIDATES as usize];
+        for candidate_id in rankings.iter() {
+            if *candidate_id >= election.candidate_count || !self.is_candidate_active(election_id, *candidate_id) {
+                return Err(b"Invalid candidate".to_vec());
+            }
+            let index = candidate_id.as_usize();
+            if seen[index] {
+                return Err(b"Duplicate ranking".to_vec());
+            }
+            seen[index] = true;
+        }
+        
+        // Mark voter as having voted, then weigh the ballot for the voter and their delegators
+        self.mark_voted(election_id, voter);
+        let weight = self.claim_ballot_weight(&election, election_id, voter)?;
+        
+        // Store ballot rankings
+        let ballot_id = self.ranked_ballot_counts.get(election_id);
+        for (rank, candidate_id) in rankings.iter().enumerate() {
+            self.ranked_ballots.get(election_id).get(ballot_id).insert(U256::from(rank), *candidate_id);
+        }
+        self.ranked_ballot_lengths.get(election_id).insert(ballot_id, U256::from(rankings.len()));
+        self.ranked_ballot_weights.get(election_id).insert(ballot_id, weight);
+        self.ranked_ballot_counts.insert(election_id, ballot_id + U256::from(1u32));
+        
+        // Record first preference so get_vote_count shows live first-choice totals, and file the
+        // ballot under its first choice so the count only revisits it if that candidate is eliminated
+        let first_choice = rankings[0];
+        let current_votes = self.votes.get(election_id).get(first_choice);
+        self.votes.get(election_id).insert(first_choice, current_votes + weight);
+        
+        let bucket_size = self.first_preference_counts.get(election_id).get(first_choice);
+        self.first_preference_ballots.get(election_id).get(first_choice).insert(bucket_size, ballot_id);
+        self.first_preference_counts.get(election_id).insert(first_choice, bucket_size + U256::from(1u32));
+        
+        Ok(())
+    }
+    
+    // Finalize election results
+    #[payable(false)]
+    pub fn finalize_election(&mut self, election_id: U256) -> Result<ElectionResult, Vec<u8>> {
+        // Only admin can finalize elections
+        if msg::sender() != self.admin.get() {
+            return Err(b"Not admin".to_vec());
+        }
+        
+        // Get election from storage
+        let mut election = self.get_election(election_id)?;
+        
+        // Ensure election wasn't cancelled and has ended
+        if election.cancelled {
+            return Err(b"Election cancelled".to_vec());
+        }
+        
+        let current_time = block_timestamp();
+        if current_time <= election.end_time {
+            return Err(b"Election still active".to_vec());
+        }
+        
+        // Ensure the reveal phase of a secret-ballot election has ended
+        if current_time <= election.reveal_end_time {
+            return Err(b"Reveal phase still active".to_vec());
+        }
+        
+        // Ensure election isn't already finalized
+        if election.finalized {
+            return Err(b"Already finalized".to_vec());
+        }
+        
+        // Ensure at least one candidate is on the ballot
+        if !self.has_active_candidate(election_id, &election) {
+            return Err(b"No candidates".to_vec());
+        }
+        
+        // Count votes according to the ballot type
+        let mut result = match election.ballot_type {
+            BALLOT_RANKED => self.tally_instant_runoff(election_id, &election),
+            BALLOT_PROPOSITION => self.tally_proposition(election_id, &election),
+            _ => self.tally_seats(election_id, &election),
+        };
+        
+        // Report sealed ballots that were never revealed
+        result.unrevealed_commits = self.commit_counts.get(election_id) - self.reveal_counts.get(election_id);
+        
+        // Record a tie at the seat cutoff; it is broken later per the election's policy
+        if result.tied_candidates.len() > 1 {
+            result.has_winner = false;
+            
+            for (i, candidate_id) in result.tied_candidates.iter().enumerate() {
+                self.tied_candidates.get(election_id).insert(U256::from(i), *candidate_id);
+            }
+            
+            let tie = TieRecord {
+                policy: election.tie_break_policy,
+                tied_count: U256::from(result.tied_candidates.len()),
+                open_seats: result.seats - U256::from(result.winners.len()),
+                resolved: false,
+                lot_winner: U256::ZERO,
+                lot_randomness: [0; 32],
+                runoff_election_id: U256::ZERO,
+            };
+            self.ties.insert(election_id, tie);
+        }
+        
+        // Report turnout against the election's own electorate
+        result.participants = self.participant_counts.get(election_id);
+        result.electorate = self.electorate_size(&election);
+        if result.electorate != U256::ZERO {
+            result.turnout_basis_points = result.participants * U256::from(10_000u32) / result.electorate;
+        }
+        
+        // District tags may change again once no election depends on them
+        if election.eligibility == ELIGIBILITY_DISTRICT {
+            self.release_district(election.district);
+        }
+        
+        // Persist the official result
+        self.store_result(&result);
+        
+        // Mark election as finalized
+        election.finalized = true;
+        self.elections.insert(election_id, election);
+        
+        // Return election results
+        Ok(result)
+    }
+    
+    // Break a recorded tie by lot by revealing the committed randomness (e.g., from a public draw),
+    // drawing one tied candidate per open seat
+    #[payable(false)]
+    pub fn draw_lot(&mut self, election_id: U256, randomness: [u8; 32]) -> Result<Vec<U256>, Vec<u8>> {
+        // Only admin can draw lots
+        if msg::sender() != self.admin.get() {
+            return Err(b"Not admin".to_vec());
+        }
+        
+        // Ensure the election ended in an unresolved tie under the lot policy
+        let mut tie = self.get_tie(election_id)?;
+        if tie.policy != TIE_LOT {
+            return Err(b"Tie policy is not lot".to_vec());
+        }
+        if tie.resolved {
+            return Err(b"Tie already resolved".to_vec());
+        }
+        
+        // Ensure the randomness matches the commitment made at creation
+        if stylus_sdk::crypto::keccak(&randomness).0 != self.lot_commitments.get(election_id) {
+            return Err(b"Randomness mismatch".to_vec());
+        }
+        
+        // Draw tied candidates without replacement; the first draw hashes the randomness
+        // and election ID, and each later draw rehashes the previous one
+        let mut pool = self.get_tied_candidates(election_id);
+        let mut seed = Vec::new();
+        seed.extend_from_slice(&randomness);
+        seed.extend_from_slice(&election_id.to_be_bytes::<32>());
+        let mut hash = stylus_sdk::crypto::keccak(&seed).0;
+        
+        let mut winners = Vec::new();
+        for i in 0..tie.open_seats.as_usize() {
+            if i > 0 {
+                hash = stylus_sdk::crypto::keccak(&hash).0;
+            }
+            let index = (U256::from_be_bytes(hash) % U256::from(pool.len())).as_usize();
+            let winner = pool.swap_remove(index);
+            self.lot_winners.get(election_id).insert(U256::from(i), winner);
+            winners.push(winner);
+        }
+        
+        // Record the draw
+        tie.resolved = true;
+        tie.lot_winner = winners[0];
+        tie.lot_randomness = randomness;
+        self.ties.insert(election_id, tie);
+        
+        // Return the drawn candidates
+        Ok(winners)
+    }
+    
+    // Cast an approval ballot selecting any number of candidates (approval elections)
+    #[payable(false)]
+    pub fn cast_approval_vote(&mut self, election_id: U256, candidate_ids: Vec<U256>) -> Result<(), Vec<u8>> {
+        let voter = msg::sender();
+        
+        // Get election from storage
+        let election = self.get_election(election_id)?;
+        
+        // Ensure election is active
+        let current_time = block_timestamp();
+        if election.cancelled || current_time < election.start_time || current_time > election.end_time {
+            return Err(b"Election not active".to_vec());
+        }
+        
+        // Ensure election uses approval ballots
+        if election.ballot_type != BALLOT_APPROVAL {
+            return Err(b"Not an approval election".to_vec());
+        }
+        
+        // Ensure voter is registered (or proven against the voter root) and eligible
+        self.authorize_voter(&election, voter)?;
+        
+        // Ensure voter hasn't already voted or handed their vote to a proxy
+        if self.ballots.get(election_id).get(voter) {
+            return Err(b"Already voted".to_vec());
+        }
+        
+        if self.effective_delegate(election_id, voter) != Address::ZERO {
+            return Err(b"Vote delegated".to_vec());
+        }
+        
+        // Validate selection: at least one candidate, in ascending order so none repeats
+        if candidate_ids.is_empty() {
+            return Err(b"No candidates selected".to_vec());
+        }
+        
+        for (i, candidate_id) in candidate_ids.iter().enumerate() {
+            if *candidate_id >= election.candidate_count || !self.is_candidate_active(election_id, *candidate_id) {
+                return Err(b"Invalid candidate".to_vec());
+            }
+            if i > 0 && *candidate_id <= candidate_ids[i - 1] {
+                return Err(b"Selection not ascending".to_vec());
+            }
+        }
+        
+        // Mark voter as having voted, then weigh the ballot for the voter and their delegators
+        self.mark_voted(election_id, voter);
+        let weight = self.claim_ballot_weight(&election, election_id, voter)?;
+        
+        // Record an approval for every selected candidate
+        for candidate_id in candidate_ids.iter() {
+            let current_votes = self.votes.get(election_id).get(*candidate_id);
+            self.votes.get(election_id).insert(*candidate_id, current_votes + weight);
+        }
+        let ballot_weights = self.approval_ballot_weights.get(election_id);
+        self.approval_ballot_weights.insert(election_id, ballot_weights + weight);
+        
+        Ok(())
+    }
+    
+    // Get the official result of a finalized election
+    #[payable(false)]
+    pub fn get_result(&self, election_id: U256) -> Result<ElectionResult, Vec<u8>> {
+        let summary = self.results.get(election_id);
+        if !summary.recorded {
+            return Err(b"Not finalized".to_vec());
+        }
+        
+        let mut winners = Vec::new();
+        for i in 0..summary.winner_count.as_usize() {
+            winners.push(self.result_winners.get(election_id).get(U256::from(i)));
+        }
+        
+        Ok(ElectionResult {
+            election_id,
+            has_winner: summary.has_winner,
+            winning_candidate: summary.winning_candidate,
+            winning_votes: summary.winning_votes,
+            total_votes: summary.total_votes,
+            rounds: summary.rounds,
+            seats: summary.seats,
+            winners,
+            tied_candidates: self.get_tied_candidates(election_id),
+            tally: self.get_tally(election_id)?,
+            unrevealed_commits: summary.unrevealed_commits,
+            outcome: summary.outcome,
+            participants: summary.participants,
+            electorate: summary.electorate,
+            turnout_basis_points: summary.turnout_basis_points,
+        })
+    }
+    
+    // Get every candidate's final count in a finalized election, most votes first
+    #[payable(false)]
+    pub fn get_tally(&self, election_id: U256) -> Result<Vec<CandidateTally>, Vec<u8>> {
+        let summary = self.results.get(election_id);
+        if !summary.recorded {
+            return Err(b"Not finalized".to_vec());
+        }
+        
+        let mut tally = Vec::new();
+        for i in 0..summary.tally_count.as_usize() {
+            tally.push(self.result_tallies.get(election_id).get(U256::from(i)));
+        }
+        Ok(tally)
+    }
+    
+    // Get when an election was finalized
+    #[payable(false)]
+    pub fn get_finalized_at(&self, election_id: U256) -> Result<U256, Vec<u8>> {
+        let summary = self.results.get(election_id);
+        if !summary.recorded {
+            return Err(b"Not finalized".to_vec());
+        }
+        
+        Ok(summary.finalized_at)
+    }
+    
+    // Get the number of voters who have taken part in an election so far
+    #[payable(false)]
+    pub fn get_participant_count(&self, election_id: U256) -> U256 {
+        self.participant_counts.get(election_id)
+    }
+    
+    // Get the number of registered voters
+    #[payable(false)]
+    pub fn get_registered_voter_count(&self) -> U256 {
+        self.registered_voter_count.get()
+    }
+    
+    // Get the number of voters currently eligible for an election
+    #[payable(false)]
+    pub fn get_electorate_size(&self, election_id: U256) -> Result<U256, Vec<u8>> {
+        let election = self.get_election(election_id)?;
+        Ok(self.electorate_size(&election))
+    }
+    
+    // Check if an address has voted in an election
+    #[payable(false)]
+    pub fn has_voted(&self, election_id: U256, voter: Address) -> bool {
+        self.ballots.get(election_id).get(voter)
+    }
+    
+    // Get vote count for a candidate
+    #[payable(false)]
+    pub fn get_vote_count(&self, election_id: U256, candidate_id: U256) -> U256 {
+        self.votes.get(election_id).get(candidate_id)
+    }
+    
+    // Get election details by ID
+    #[payable(false)]
+    pub fn get_election(&self, election_id: U256) -> Result<Election, Vec<u8>> {
+        let election = self.elections.get(election_id);
+        
+        // Ensure election exists
+        if election.start_time == U256::ZERO && election.end_time == U256::ZERO {
+            return Err(b"Election not found".to_vec());
+        }
+        
+        Ok(election)
+    }
+    
+    // Get number of ranked ballots cast in an election
+    #[payable(false)]
+    pub fn get_ranked_ballot_count(&self, election_id: U256) -> U256 {
+        self.ranked_ballot_counts.get(election_id)
+    }
+    
+    // Get the rankings on a ranked ballot, most preferred first
+    #[payable(false)]
+    pub fn get_ranked_ballot(&self, election_id: U256, ballot_id: U256) -> Result<Vec<U256>, Vec<u8>> {
+        // Ensure ballot ID is valid
+        if ballot_id >= self.ranked_ballot_counts.get(election_id) {
+            return Err(b"Ballot not found".to_vec());
+        }
+        
+        let length = self.ranked_ballot_lengths.get(election_id).get(ballot_id).as_usize();
+        let mut rankings = Vec::new();
+        for rank in 0..length {
+            rankings.push(self.ranked_ballots.get(election_id).get(ballot_id).get(U256::from(rank)));
+        }
+        
+        Ok(rankings)
+    }
+    
+    // Get the tie record of a finalized election
+    #[payable(false)]
+    pub fn get_tie(&self, election_id: U256) -> Result<TieRecord, Vec<u8>> {
+        let tie = self.ties.get(election_id);
+        
+        // Ensure a tie was recorded (at least two candidates tied)
+        if tie.tied_count == U256::ZERO {
+            return Err(b"No tie recorded".to_vec());
+        }
+        
+        Ok(tie)
+    }
+    
+    // Get the candidates tied at the seat cutoff in a finalized election
+    #[payable(false)]
+    pub fn get_tied_candidates(&self, election_id: U256) -> Vec<U256> {
+        let tied_count = self.ties.get(election_id).tied_count.as_usize();
+        let mut candidates = Vec::new();
+        for i in 0..tied_count {
+            candidates.push(self.tied_candidates.get(election_id).get(U256::from(i)));
+        }
+        candidates
+    }
+    
+    // Get the candidates drawn by lot, in draw order
+    #[payable(false)]
+    pub fn get_lot_winners(&self, election_id: U256) -> Vec<U256> {
+        let tie = self.ties.get(election_id);
+        let mut winners = Vec::new();
+        if tie.resolved && tie.policy == TIE_LOT {
+            for i in 0..tie.open_seats.as_usize() {
+                winners.push(self.lot_winners.get(election_id).get(U256::from(i)));
+            }
+        }
+        winners
+    }
+    
+    // Get the original candidate ID for a runoff election candidate
+    #[payable(false)]
+    pub fn get_runoff_candidate(&self, runoff_election_id: U256, candidate_id: U256) -> Result<U256, Vec<u8>> {
+        let election = self.get_election(runoff_election_id)?;
+        
+        // Ensure candidate ID is valid
+        if candidate_id >= election.candidate_count {
+            return Err(b"Invalid candidate".to_vec());
+        }
+        
+        Ok(self.runoff_candidates.get(runoff_election_id).get(candidate_id))
+    }
+    
+    // Compute the commitment a voter submits for a sealed ballot
+    #[payable(false)]
+    pub fn compute_commitment(
+        &self,
+        election_id: U256,
+        voter: Address,
+        candidate_id: U256,
+        salt: [u8; 32],
+    ) -> [u8; 32] {
+        // Binding the election and voter stops commitments being copied
+        let mut encoded = Vec::new();
+        encoded.extend_from_slice(&election_id.to_be_bytes::<32>());
+        encoded.extend_from_slice(voter.as_slice());
+        encoded.extend_from_slice(&candidate_id.to_be_bytes::<32>());
+        encoded.extend_from_slice(&salt);
+        stylus_sdk::crypto::keccak(&encoded).0
+    }
+    
+    // Check if a voter has revealed their sealed ballot
+    #[payable(false)]
+    pub fn has_revealed(&self, election_id: U256, voter: Address) -> bool {
+        self.revealed.get(election_id).get(voter)
+    }
+    
+    // Get numbers of sealed ballots committed and revealed in an election
+    #[payable(false)]
+    pub fn get_commit_reveal_counts(&self, election_id: U256) -> (U256, U256) {
+        (self.commit_counts.get(election_id), self.reveal_counts.get(election_id))
+    }
+    
+    // Check if a voter is eligible to vote in an election (Merkle elections: once proven)
+    #[payable(false)]
+    pub fn is_eligible(&self, election_id: U256, voter: Address) -> Result<bool, Vec<u8>> {
+        let election = self.get_election(election_id)?;
+        Ok(self.authorize_voter(&election, voter).is_ok())
+    }
+    
+    // Check a Merkle proof for a voter's (address, id_hash) leaf without recording it
+    #[payable(false)]
+    pub fn verify_voter_proof(
+        &self,
+        election_id: U256,
+        voter: Address,
+        id_hash: [u8; 32],
+        proof: Vec<[u8; 32]>,
+    ) -> Result<bool, Vec<u8>> {
+        let election = self.get_election(election_id)?;
+        
+        // Leaf is keccak256(abi.encode(address, bytes32))
+        let mut leaf_data = [0u8; 64];
+        leaf_data[12..32].copy_from_slice(voter.as_slice());
+        leaf_data[32..].copy_from_slice(&id_hash);
+        let mut node = stylus_sdk::crypto::keccak(&leaf_data).0;
+        
+        // Hash up the tree with sorted pairs
+        for sibling in proof.iter() {
+            let mut pair = [0u8; 64];
+            if node <= *sibling {
+                pair[..32].copy_from_slice(&node);
+                pair[32..].copy_from_slice(sibling);
+            } else {
+                pair[..32].copy_from_slice(sibling);
+                pair[32..].copy_from_slice(&node);
+            }
+            node = stylus_sdk::crypto::keccak(&pair).0;
+        }
+        
+        Ok(node == election.voter_merkle_root)
+    }
+    
+    // Get a voter's constituency/district tag
+    #[payable(false)]
+    pub fn get_voter_district(&self, voter: Address) -> [u8; 32] {
+        self.voter_districts.get(voter)
+    }
+    
+    // Get a candidate's registry entry
+    #[payable(false)]
+    pub fn get_candidate(&self, election_id: U256, candidate_id: U256) -> Result<Candidate, Vec<u8>> {
+        let election = self.get_election(election_id)?;
+        
+        // Ensure candidate ID is valid
+        if candidate_id >= election.candidate_count {
+            return Err(b"Invalid candidate".to_vec());
+        }
+        
+        Ok(self.candidates.get(election_id).get(candidate_id))
+    }
+    
+    // Check if a candidate is on the ballot
+    #[payable(false)]
+    pub fn is_candidate_active(&self, election_id: U256, candidate_id: U256) -> bool {
+        self.candidates.get(election_id).get(candidate_id).status == CANDIDATE_ACTIVE
+    }
+    
+    // Get a voter's direct proxy for an election (the election's proxy, else the global one)
+    #[payable(false)]
+    pub fn get_delegate(&self, election_id: U256, voter: Address) -> Address {
+        self.effective_delegate(election_id, voter)
+    }
+    
+    // Follow a voter's delegation chain to the proxy who will cast their vote
+    #[payable(false)]
+    pub fn resolve_delegate(&self, election_id: U256, voter: Address) -> Result<Address, Vec<u8>> {
+        let mut current = voter;
+        for _ in 0..MAX_DELEGATION_DEPTH {
+            let next = self.effective_delegate(election_id, current);
+            if next == Address::ZERO {
+                return Ok(current);
+            }
+            if next == voter {
+                return Err(b"Delegation loop".to_vec());
+            }
+            current = next;
+        }
+        Err(b"Delegation chain too long".to_vec())
+    }
+    
+    // Weight a vote from this voter would currently carry (own weight plus delegators')
+    #[payable(false)]
+    pub fn get_voting_weight(&self, election_id: U256, voter: Address) -> Result<U256, Vec<u8>> {
+        let election = self.get_election(election_id)?;
+        let mut delegators = Vec::new();
+        self.collect_delegators(&election, election_id, voter, 0, &mut delegators);
+        
+        let mut weight = self.voter_weight(&election, voter)?;
+        for delegator in delegators.iter() {
+            weight = weight + self.voter_weight(&election, *delegator)?;
+        }
+        Ok(weight)
+    }
+    
+    // Get a voter's own weight in an election (1 under equal weighting)
+    #[payable(false)]
+    pub fn get_voter_weight(&self, election_id: U256, voter: Address) -> Result<U256, Vec<u8>> {
+        let election = self.get_election(election_id)?;
+        self.voter_weight(&election, voter)
+    }
+    
+    // Get an election's status (0=scheduled, 1=active, 2=ended, 3=finalized, 4=cancelled)
+    #[payable(false)]
+    pub fn get_election_status(&self, election_id: U256) -> Result<u8, Vec<u8>> {
+        let election = self.get_election(election_id)?;
+        Ok(self.election_status(&election))
+    }
+    
+    // Get the number of lifecycle changes recorded for an election
+    #[payable(false)]
+    pub fn get_lifecycle_change_count(&self, election_id: U256) -> U256 {
+        self.lifecycle_change_counts.get(election_id)
+    }
+    
+    // Get a recorded lifecycle change
+    #[payable(false)]
+    pub fn get_lifecycle_change(&self, election_id: U256, index: U256) -> Result<LifecycleChange, Vec<u8>> {
+        if index >= self.lifecycle_change_counts.get(election_id) {
+            return Err(b"Change not found".to_vec());
+        }
+        
+        Ok(self.lifecycle_changes.get(election_id).get(index))
+    }
+    
+    // Check if a voter is registered
+    #[payable(false)]
+    pub fn is_registered(&self, voter: Address) -> bool {
+        self.voter_registry.get(voter) != [0; 32]
+    }
+    
+    // Get voter ID hash
+    #[payable(false)]
+    pub fn get_voter_id_hash(&self, voter: Address) -> Result<[u8; 32], Vec<u8>> {
+        let id_hash = self.voter_registry.get(voter);
+        
+        // Ensure voter is registered
+        if id_hash == [0; 32] {
+            return Err(b"Voter not registered".to_vec());
+        }
+        
+        Ok(id_hash)
+    }
+}
+
+// Internal helpers
+impl VotingSystem {
+    // Validate and store a new election
+    fn open_election(
+        &mut self,
+        name: [u8; 32],
+        candidate_count: U256,
+        start_time: U256,
+        end_time: U256,
+        metadata_hash: [u8; 32],
+        ballot_type: u8,
+        tie_break_policy: u8,
+        lot_commitment: [u8; 32],
+        reveal_end_time: U256,
+        weighting: u8,
+        weight_token: Address,
+        snapshot_time: U256,
+        seats: U256,
+    ) -> Result<U256, Vec<u8>> {
+        // Validate inputs (candidate_count may be 0 when candidates are added or nominated later)
+        if end_time <= start_time {
+            return Err(b"Invalid time period".to_vec());
+        }
+        
+        if ballot_type > BALLOT_PROPOSITION {
+            return Err(b"Invalid ballot type".to_vec());
+        }
+        
+        if seats == U256::ZERO {
+            return Err(b"Invalid seat count".to_vec());
+        }
+        
+        if ballot_type == BALLOT_RANKED && seats != U256::from(1u32) {
+            return Err(b"Ranked-choice fills one seat".to_vec());
+        }
+        
+        if ballot_type == BALLOT_RANKED && candidate_count > U256::from(MAX_RANKED_CANDIDATES) {
+            return Err(b"Too many candidates".to_vec());
+        }
+        
+        if tie_break_policy > TIE_LOT {
+            return Err(b"Invalid tie-break policy".to_vec());
+        }
+        
+        // Lot draws reveal randomness committed now, before anyone can foresee a tie
+        if tie_break_policy == TIE_LOT && lot_commitment == [0; 32] {
+            return Err(b"No lot commitment".to_vec());
+        }
+        
+        // Secret ballots need a reveal phase after voting, and take a single choice
+        if reveal_end_time != U256::ZERO {
+            if reveal_end_time <= end_time {
+                return Err(b"Invalid reveal period".to_vec());
+            }
+            if ballot_type != BALLOT_PLURALITY && ballot_type != BALLOT_PROPOSITION {
+                return Err(b"Secret ballots must be single-choice".to_vec());
+            }
+        }
+        
+        // Weighted elections capture weights at a snapshot before voting opens
+        if weighting > WEIGHT_ASSIGNED {
+            return Err(b"Invalid weighting".to_vec());
+        }
+        
+        if weighting == WEIGHT_TOKEN && weight_token == Address::ZERO {
+            return Err(b"Invalid weight token".to_vec());
+        }
+        
+        // The snapshot is read in the token's own clock: a timestamp for timestamp-clock tokens,
+        // otherwise a block number, which must already be mined since blocks can't be timed ahead
+        if weighting != WEIGHT_EQUAL {
+            let block_clock = weighting == WEIGHT_TOKEN && !self.token_uses_timestamps(weight_token);
+            let valid = if block_clock {
+                snapshot_time < block_number()
+            } else {
+                snapshot_time < start_time
+            };
+            if snapshot_time == U256::ZERO || !valid {
+                return Err(b"Invalid snapshot time".to_vec());
+            }
+        }
+        
+        // Get current election ID and increment counter
+        let election_id = self.election_counter.get();
+        self.election_counter.set(election_id + U256::from(1u32));
+        
+        // Create new election
+        let election = Election {
+            name,
+            candidate_count,
+            start_time,
+            end_time,
+            finalized: false,
+            cancelled: false,
+            metadata_hash,
+            ballot_type,
+            seats,
+            tie_break_policy,
+            reveal_end_time,
+            eligibility: ELIGIBILITY_ALL,
+            district: [0; 32],
+            voter_merkle_root: [0; 32],
+            nomination_end_time: U256::ZERO,
+            endorsement_threshold: U256::ZERO,
+            weighting,
+            weight_token,
+            snapshot_time,
+            weight_source: election_id,
+            quorum: U256::ZERO,
+            threshold: THRESHOLD_SIMPLE,
+            eligibility_source: election_id,
+            merkle_voter_count: U256::ZERO,
+        };
+        
+        // Save election and lot commitment in storage
+        self.elections.insert(election_id, election);
+        if tie_break_policy == TIE_LOT {
+            self.lot_commitments.insert(election_id, lot_commitment);
+        }
+        
+        // Return the election ID
+        Ok(election_id)
+    }
+    
+    // Mark a voter as having taken part and count them toward turnout
+    fn mark_voted(&mut self, election_id: U256, voter: Address) {
+        self.ballots.get(election_id).insert(voter, true);
+        let participants = self.participant_counts.get(election_id);
+        self.participant_counts.insert(election_id, participants + U256::from(1u32));
+    }
+    
+    // Persist a finalized result (tied candidates are already stored with the tie record)
+    fn store_result(&mut self, result: &ElectionResult) {
+        let election_id = result.election_id;
+        
+        for (i, candidate_id) in result.winners.iter().enumerate() {
+            self.result_winners.get(election_id).insert(U256::from(i), *candidate_id);
+        }
+        
+        for (i, entry) in result.tally.iter().enumerate() {
+            self.result_tallies.get(election_id).insert(U256::from(i), entry.clone());
+        }
+        
+        let summary = ResultSummary {
+            recorded: true,
+            has_winner: result.has_winner,
+            winning_candidate: result.winning_candidate,
+            winning_votes: result.winning_votes,
+            total_votes: result.total_votes,
+            rounds: result.rounds,
+            seats: result.seats,
+            unrevealed_commits: result.unrevealed_commits,
+            outcome: result.outcome,
+            participants: result.participants,
+            electorate: result.electorate,
+            turnout_basis_points: result.turnout_basis_points,
+            winner_count: U256::from(result.winners.len()),
+            tally_count: U256::from(result.tally.len()),
+            finalized_at: block_timestamp(),
+        };
+        self.results.insert(election_id, summary);
+    }
+    
+    // Current lifecycle status of an election
+    fn election_status(&self, election: &Election) -> u8 {
+        let current_time = block_timestamp();
+        if election.cancelled {
+            STATUS_CANCELLED
+        } else if election.finalized {
+            STATUS_FINALIZED
+        } else if current_time < election.start_time {
+            STATUS_SCHEDULED
+        } else if current_time <= election.end_time {
+            STATUS_ACTIVE
+        } else {
+            STATUS_ENDED
+        }
+    }
+    
+    // Record an admin change against an election, with the state it replaces
+    fn record_change(&mut self, election_id: U256, election: &Election, action: u8, reason: [u8; 32]) {
+        let change = LifecycleChange {
+            action,
+            reason,
+            changed_at: block_timestamp(),
+            previous_start_time: election.start_time,
+            previous_end_time: election.end_time,
+            previous_metadata_hash: election.metadata_hash,
+        };
+        
+        let index = self.lifecycle_change_counts.get(election_id);
+        self.lifecycle_changes.get(election_id).insert(index, change);
+        self.lifecycle_change_counts.insert(election_id, index + U256::from(1u32));
+    }
+    
+    // Append a candidate to an election's registry before voting opens
+    fn register_candidate(
+        &mut self,
+        election_id: U256,
+        name: [u8; 32],
+        party: [u8; 32],
+        metadata_hash: [u8; 32],
+        nominee: Address,
+        status: u8,
+    ) -> Result<U256, Vec<u8>> {
+        // Get election from storage
+        let mut election = self.get_election(election_id)?;
+        
+        // Ensure voting hasn't started
+        if block_timestamp() >= election.start_time {
+            return Err(b"Election already started".to_vec());
+        }
+        
+        // Proposition choices are fixed
+        if election.ballot_type == BALLOT_PROPOSITION {
+            return Err(b"Not a candidate election".to_vec());
+        }
+        
+        if name == [0; 32] {
+            return Err(b"Empty name".to_vec());
+        }
+        
+        if election.ballot_type == BALLOT_RANKED && election.candidate_count >= U256::from(MAX_RANKED_CANDIDATES) {
+            return Err(b"Too many candidates".to_vec());
+        }
+        
+        // Assign the next candidate ID
+        let candidate_id = election.candidate_count;
+        election.candidate_count = candidate_id + U256::from(1u32);
+        self.elections.insert(election_id, election);
+        
+        // Save candidate
+        let candidate = Candidate {
+            name,
+            party,
+            metadata_hash,
+            status,
+            nominee,
+            endorsement_count: U256::ZERO,
+        };
+        self.candidates.get(election_id).insert(candidate_id, candidate);
+        
+        // Return the candidate ID
+        Ok(candidate_id)
+    }
+    
+    // Record a proxy in a delegation scope and return the end of the resulting chain
+    fn set_delegate(&mut self, scope: U256, delegator: Address, delegate: Address) -> Result<Address, Vec<u8>> {
+        // Ensure both parties are registered
+        if self.voter_registry.get(delegator) == [0; 32] {
+            return Err(b"Voter not registered".to_vec());
+        }
+        
+        if self.voter_registry.get(delegate) == [0; 32] {
+            return Err(b"Delegate not registered".to_vec());
+        }
+        
+        if delegate == delegator {
+            return Err(b"Self-delegation".to_vec());
+        }
+        
+        // Follow the delegate's chain in this scope, rejecting loops back to the delegator.
+        // A global chain must not pass through anyone with a live per-election proxy: those links
+        // aren't followed here, and could lead back to the delegator in that election
+        let mut final_delegate = delegate;
+        let mut depth = 1;
+        loop {
+            if scope == GLOBAL_DELEGATION && self.has_live_election_proxy(final_delegate) {
+                return Err(b"Chain has election proxy".to_vec());
+            }
+            let next = if scope == GLOBAL_DELEGATION {
+                self.delegates.get(GLOBAL_DELEGATION).get(final_delegate)
+            } else {
+                self.effective_delegate(scope, final_delegate)
+            };
+            if next == Address::ZERO {
+                break;
+            }
+            if next == delegator {
+                return Err(b"Delegation loop".to_vec());
+            }
+            depth += 1;
+            if depth > MAX_DELEGATION_DEPTH {
+                return Err(b"Delegation chain too long".to_vec());
+            }
+            final_delegate = next;
+        }
+        
+        // Move the delegator from their previous delegate's list to this one, so votes can find them
+        let previous = self.delegates.get(scope).get(delegator);
+        if previous != Address::ZERO && previous != delegate {
+            self.unlist_delegator(scope, previous, delegator);
+        }
+        if self.delegator_indexes.get(scope).get(delegate).get(delegator) == U256::ZERO {
+            let count = self.delegator_counts.get(scope).get(delegate);
+            if count >= U256::from(MAX_DELEGATORS) {
+                return Err(b"Too many delegators".to_vec());
+            }
+            self.delegators.get(scope).get(delegate).insert(count, delegator);
+            self.delegator_counts.get(scope).insert(delegate, count + U256::from(1u32));
+            self.delegator_indexes.get(scope).get(delegate).insert(delegator, count + U256::from(1u32));
+        }
+        
+        // Save delegation
+        self.delegates.get(scope).insert(delegator, delegate);
+        
+        Ok(final_delegate)
+    }
+    
+    // Remove a delegator from a delegate's list, freeing their slot
+    fn unlist_delegator(&mut self, scope: U256, delegate: Address, delegator: Address) {
+        let index = self.delegator_indexes.get(scope).get(delegate).get(delegator);
+        if index == U256::ZERO {
+            return;
+        }
+        
+        // Move the last entry into the gap
+        let last = self.delegator_counts.get(scope).get(delegate) - U256::from(1u32);
+        let moved = self.delegators.get(scope).get(delegate).get(last);
+        self.delegators.get(scope).get(delegate).insert(index - U256::from(1u32), moved);
+        self.delegator_indexes.get(scope).get(delegate).insert(moved, index);
+        
+        self.delegators.get(scope).get(delegate).insert(last, Address::ZERO);
+        self.delegator_counts.get(scope).insert(delegate, last);
+        self.delegator_indexes.get(scope).get(delegate).insert(delegator, U256::ZERO);
+    }
+    
+    // Check if a voter's per-election proxy can still vote for them: set, not yet used,
+    // and in an election that hasn't been cancelled, finalized or closed
+    fn is_election_proxy_live(&self, election_id: U256, voter: Address) -> bool {
+        if self.delegates.get(election_id).get(voter) == Address::ZERO || self.ballots.get(election_id).get(voter) {
+            return false;
+        }
+        let election = self.elections.get(election_id);
+        !election.cancelled && !election.finalized && block_timestamp() <= election.end_time
+    }
+    
+    // Check if a voter has a live per-election proxy in any election
+    fn has_live_election_proxy(&self, voter: Address) -> bool {
+        let count = self.election_proxy_counts.get(voter);
+        let mut i = U256::ZERO;
+        while i < count {
+            if self.is_election_proxy_live(self.election_proxies.get(voter).get(i), voter) {
+                return true;
+            }
+            i = i + U256::from(1u32);
+        }
+        false
+    }
+    
+    // Drop elections whose proxy has lapsed from a voter's election proxy list
+    fn prune_election_proxies(&mut self, voter: Address) {
+        let mut count = self.election_proxy_counts.get(voter);
+        let mut i = U256::ZERO;
+        while i < count {
+            let election_id = self.election_proxies.get(voter).get(i);
+            if self.is_election_proxy_live(election_id, voter) {
+                i = i + U256::from(1u32);
+                continue;
+            }
+            
+            // Move the last entry into the gap
+            count = count - U256::from(1u32);
+            let last = self.election_proxies.get(voter).get(count);
+            self.election_proxies.get(voter).insert(i, last);
+        }
+        self.election_proxy_counts.insert(voter, count);
+    }
+    
+    // Check if anyone currently has the voter as their global proxy
+    fn has_global_delegators(&self, voter: Address) -> bool {
+        let count = self.delegator_counts.get(GLOBAL_DELEGATION).get(voter);
+        let mut i = U256::ZERO;
+        while i < count {
+            let delegator = self.delegators.get(GLOBAL_DELEGATION).get(voter).get(i);
+            if self.delegates.get(GLOBAL_DELEGATION).get(delegator) == voter {
+                return true;
+            }
+            i = i + U256::from(1u32);
+        }
+        false
+    }
+    
+    // A voter's proxy for an election: the election's proxy if set, else the global one
+    fn effective_delegate(&self, election_id: U256, voter: Address) -> Address {
+        let delegate = self.delegates.get(election_id).get(voter);
+        if delegate != Address::ZERO {
+            return delegate;
+        }
+        self.delegates.get(GLOBAL_DELEGATION).get(voter)
+    }
+    
+    // Gather every eligible delegator, direct or via a chain, whose vote a ballot from `delegate` carries.
+    // Chains are followed at most MAX_DELEGATION_DEPTH links, and each delegator is gathered once
+    fn collect_delegators(
+        &self,
+        election: &Election,
+        election_id: U256,
+        delegate: Address,
+        depth: u32,
+        found: &mut Vec<Address>,
+    ) {
+        if depth >= MAX_DELEGATION_DEPTH {
+            return;
+        }
+        
+        for scope in [election_id, GLOBAL_DELEGATION] {
+            let count = self.delegator_counts.get(scope).get(delegate);
+            let mut i = U256::ZERO;
+            while i < count {
+                let delegator = self.delegators.get(scope).get(delegate).get(i);
+                i = i + U256::from(1u32);
+                
+                // Skip revoked or superseded delegations, and delegators who voted themselves
+                let listed_here = if scope == GLOBAL_DELEGATION {
+                    self.delegates.get(election_id).get(delegator) == Address::ZERO
+                        && self.delegates.get(GLOBAL_DELEGATION).get(delegator) == delegate
+                } else {
+                    self.delegates.get(election_id).get(delegator) == delegate
+                };
+                if !listed_here || self.ballots.get(election_id).get(delegator) || found.contains(&delegator) {
+                    continue;
+                }
+                
+                // Delegators must be eligible for the election in their own right
+                if self.authorize_voter(election, delegator).is_err() {
+                    continue;
+                }
+                
+                found.push(delegator);
+                self.collect_delegators(election, election_id, delegator, depth + 1, found);
+            }
+        }
+    }
+    
+    // Mark a voter's delegators as voted and return the ballot's weight (voter plus delegators)
+    fn claim_ballot_weight(&mut self, election: &Election, election_id: U256, voter: Address) -> Result<U256, Vec<u8>> {
+        let mut delegators = Vec::new();
+        self.collect_delegators(election, election_id, voter, 0, &mut delegators);
+        
+        let mut weight = self.voter_weight(election, voter)?;
+        for delegator in delegators.iter() {
+            weight = weight + self.voter_weight(election, *delegator)?;
+            self.mark_voted(election_id, *delegator);
+        }
+        
+        if weight == U256::ZERO {
+            return Err(b"No voting weight".to_vec());
+        }
+        
+        Ok(weight)
+    }
+    
+    // Check if a vote token's ERC-6372 clock counts in timestamps rather than block numbers
+    fn token_uses_timestamps(&self, token: Address) -> bool {
+        let output = match static_call(Call::new(), token, &CLOCK_MODE_SELECTOR) {
+            Ok(output) => output,
+            Err(_) => return false,
+        };
+        
+        // Decode the returned string (offset, length, bytes)
+        if output.len() < 64 {
+            return false;
+        }
+        let length = U256::from_be_slice(&output[32..64]);
+        let mode: &[u8] = b"mode=timestamp";
+        length == U256::from(mode.len())
+            && output.len() >= 64 + mode.len()
+            && &output[64..64 + mode.len()] == mode
+    }
+    
+    // A single voter's weight as captured at the election's snapshot
+    fn voter_weight(&self, election: &Election, voter: Address) -> Result<U256, Vec<u8>> {
+        match election.weighting {
+            WEIGHT_TOKEN => {
+                // getPastVotes(voter, snapshot_time), in the token's clock: voting power can't move after the snapshot
+                let mut input = Vec::with_capacity(68);
+                input.extend_from_slice(&GET_PAST_VOTES_SELECTOR);
+                input.extend_from_slice(&[0u8; 12]);
+                input.extend_from_slice(voter.as_slice());
+                input.extend_from_slice(&election.snapshot_time.to_be_bytes::<32>());
+                
+                let output = static_call(Call::new(), election.weight_token, &input)
+                    .map_err(|_| b"Weight lookup failed".to_vec())?;
+                if output.len() != 32 {
+                    return Err(b"Weight lookup failed".to_vec());
+                }
+                Ok(U256::from_be_slice(&output))
+            }
+            WEIGHT_ASSIGNED => Ok(self.voter_weights.get(election.weight_source).get(voter)),
+            _ => Ok(U256::from(1u32)),
+        }
+    }
+    
+    // Check if any candidate is on the ballot
+    fn has_active_candidate(&self, election_id: U256, election: &Election) -> bool {
+        (0..election.candidate_count.as_usize()).any(|i| self.is_candidate_active(election_id, U256::from(i)))
+    }
+    
+    // Record an unfinalized election restricted to a district (the freeze keeps the earliest start)
+    fn track_district(&mut self, district: [u8; 32], start_time: U256) {
+        let count = self.district_election_counts.get(district);
+        if count == U256::ZERO || start_time < self.district_frozen_from.get(district) {
+            self.district_frozen_from.insert(district, start_time);
+        }
+        self.district_election_counts.insert(district, count + U256::from(1u32));
+    }
+    
+    // Release a district when an election restricted to it is finalized, cancelled or re-scoped
+    fn release_district(&mut self, district: [u8; 32]) {
+        let count = self.district_election_counts.get(district);
+        self.district_election_counts.insert(district, count - U256::from(1u32));
+    }
+    
+    // Check if a district's voter tags are frozen by an election under way
+    fn is_district_frozen(&self, district: [u8; 32]) -> bool {
+        district != [0; 32]
+            && self.district_election_counts.get(district) != U256::ZERO
+            && block_timestamp() >= self.district_frozen_from.get(district)
+    }
+    
+    // Number of voters eligible under an election's eligibility mode
+    fn electorate_size(&self, election: &Election) -> U256 {
+        match election.eligibility {
+            ELIGIBILITY_LIST => self.eligible_voter_counts.get(election.eligibility_source),
+            ELIGIBILITY_DISTRICT => self.district_voter_counts.get(election.district),
+            ELIGIBILITY_MERKLE => election.merkle_voter_count,
+            _ => self.registered_voter_count.get(),
+        }
+    }
+    
+    // Ensure a voter may vote under an election's eligibility mode
+    fn authorize_voter(&self, election: &Election, voter: Address) -> Result<(), Vec<u8>> {
+        // Merkle elections replace individual registration with a proof
+        if election.eligibility == ELIGIBILITY_MERKLE {
+            if !self.merkle_verified.get(election.eligibility_source).get(voter) {
+                return Err(b"Voter not verified".to_vec());
+            }
+            return Ok(());
+        }
+        
+        // Ensure voter is registered
+        if self.voter_registry.get(voter) == [0; 32] {
+            return Err(b"Voter not registered".to_vec());
+        }
+        
+        // Ensure voter is eligible for this election
+        let eligible = match election.eligibility {
+            ELIGIBILITY_LIST => self.eligible_voters.get(election.eligibility_source).get(voter),
+            ELIGIBILITY_DISTRICT => self.voter_districts.get(voter) == election.district,
+            _ => true,
+        };
+        if !eligible {
+            return Err(b"Voter not eligible".to_vec());
+        }
+        
+        Ok(())
+    }
+    
+    // Plurality and approval count: the candidates with the most votes fill the seats
+    fn tally_seats(&self, election_id: U256, election: &Election) -> ElectionResult {
+        let mut tally = Vec::new();
+        let mut total_votes = U256::ZERO;
+        
+        for i in 0..election.candidate_count.as_usize() {
+            let candidate_id = U256::from(i as u32);
+            
+            // Withdrawn, rejected and pending candidates are left off the count
+            if !self.is_candidate_active(election_id, candidate_id) {
+                continue;
+            }
+            
+            let votes = self.votes.get(election_id).get(candidate_id);
+            
+            total_votes = total_votes + votes;
+            tally.push(CandidateTally { candidate_id, votes });
+        }
+        
+        // Approval ballots count toward several candidates, so turnout is the ballot weight
+        if election.ballot_type == BALLOT_APPROVAL {
+            total_votes = self.approval_ballot_weights.get(election_id);
+        }
+        
+        Self::sort_tally(&mut tally);
+        let (winners, tied_candidates) = Self::seat_cutoff(&tally, election.seats.as_usize());
+        
+        Self::build_result(election_id, tally, winners, tied_candidates, election.seats, total_votes, U256::from(1u32))
+    }
+    
+    // Proposition count: decided by the yes/no threshold once turnout meets the quorum
+    fn tally_proposition(&self, election_id: U256, election: &Election) -> ElectionResult {
+        let yes = self.votes.get(election_id).get(U256::from(CHOICE_YES));
+        let no = self.votes.get(election_id).get(U256::from(CHOICE_NO));
+        let abstain = self.votes.get(election_id).get(U256::from(CHOICE_ABSTAIN));
+        let total_votes = yes + no + abstain;
+        
+        // Abstentions count toward the quorum but not the threshold
+        let passed = match election.threshold {
+            THRESHOLD_TWO_THIRDS => yes > U256::ZERO && yes * U256::from(3u32) >= (yes + no) * U256::from(2u32),
+            _ => yes > no,
+        };
+        let outcome = if total_votes < election.quorum || total_votes == U256::ZERO {
+            OUTCOME_QUORUM_NOT_MET
+        } else if passed {
+            OUTCOME_PASSED
+        } else {
+            OUTCOME_FAILED
+        };
+        
+        let mut tally = Vec::new();
+        tally.push(CandidateTally { candidate_id: U256::from(CHOICE_YES), votes: yes });
+        tally.push(CandidateTally { candidate_id: U256::from(CHOICE_NO), votes: no });
+        tally.push(CandidateTally { candidate_id: U256::from(CHOICE_ABSTAIN), votes: abstain });
+        Self::sort_tally(&mut tally);
+        
+        // No candidate wins a proposition; the outcome carries the decision
+        ElectionResult {
+            election_id,
+            has_winner: false,
+            winning_candidate: U256::ZERO,
+            winning_votes: U256::ZERO,
+            total_votes,
+            rounds: U256::from(1u32),
+            seats: U256::ZERO,
+            winners: Vec::new(),
+            tied_candidates: Vec::new(),
+            tally,
+            unrevealed_commits: U256::ZERO,
+            outcome,
+            participants: U256::ZERO,
+            electorate: U256::ZERO,
+            turnout_basis_points: U256::ZERO,
+        }
+    }
+    
+    // Instant-runoff count: repeatedly eliminate the last-placed candidate and
+    // transfer their ballots until someone holds a majority of continuing ballots.
+    // Round one uses the first-preference totals recorded at casting; each later round
+    // only reads the eliminated candidate's ballots, so work grows with transfers rather
+    // than with rounds times ballots.
+    fn tally_instant_runoff(&self, election_id: U256, election: &Election) -> ElectionResult {
+        let candidate_count = election.candidate_count.as_usize();
+        let mut on_ballot = [false; MAX_RANKED_CANDIDATES as usize];
+        let mut eliminated = [false; MAX_RANKED_CANDIDATES as usize];
+        let mut counts = [U256::ZERO; MAX_RANKED_CANDIDATES as usize];
+        let mut remaining = 0;
+        let mut rounds = U256::ZERO;
+        
+        // Ballots transferred to each candidate, with the rank they now count at
+        let mut transferred: Vec<Vec<(U256, usize)>> = Vec::new();
+        
+        // Withdrawn, rejected and pending candidates start out eliminated and are left off the count
+        let mut total_votes = U256::ZERO;
+        for i in 0..candidate_count {
+            on_ballot[i] = self.is_candidate_active(election_id, U256::from(i));
+            eliminated[i] = !on_ballot[i];
+            if on_ballot[i] {
+                remaining = remaining + 1;
+            }
+            
+            // First-preference weights (proxies carry their delegators' ballots)
+            counts[i] = self.votes.get(election_id).get(U256::from(i));
+            total_votes = total_votes + counts[i];
+            transferred.push(Vec::new());
+        }
+        
+        // Ballots naming an inactive first choice count for their next continuing choice
+        let mut continuing = total_votes;
+        for i in 0..candidate_count {
+            if !on_ballot[i] {
+                continuing = continuing - self.transfer_ballots(election_id, i, &eliminated, &mut counts, &mut transferred);
+            }
+        }
+        
+        loop {
+            rounds = rounds + U256::from(1u32);
+            
+            // Find leader and last-placed continuing candidates (ties favour lower IDs)
+            let mut leader = 0;
+            let mut last = 0;
+            let mut found = false;
+            for i in 0..candidate_count {
+                if eliminated[i] {
+                    continue;
+                }
+                if !found {
+                    leader = i;
+                    last = i;
+                    found = true;
+                    continue;
+                }
+                if counts[i] > counts[leader] {
+                    leader = i;
+                }
+                if counts[i] <= counts[last] {
+                    last = i;
+                }
+            }
+            
+            // Stop on a majority of continuing ballots, when one candidate remains,
+            // or when all continuing candidates are level and no one can be eliminated fairly
+            let majority = counts[leader] * U256::from(2u32) > continuing;
+            let all_level = counts[leader] == counts[last];
+            if majority || remaining == 1 || all_level {
+                let mut tally = Vec::new();
+                let mut tied_candidates = Vec::new();
+                for i in 0..candidate_count {
+                    if !on_ballot[i] {
+                        continue;
+                    }
+                    tally.push(CandidateTally { candidate_id: U256::from(i), votes: counts[i] });
+                    if !eliminated[i] && counts[i] == counts[leader] {
+                        tied_candidates.push(U256::from(i));
+                    }
+                }
+                Self::sort_tally(&mut tally);
+                
+                // A single leader takes the seat; level leaders tie for it
+                let (winners, tied_candidates) = if tied_candidates.len() > 1 {
+                    (Vec::new(), tied_candidates)
+                } else {
+                    (tied_candidates, Vec::new())
+                };
+                return Self::build_result(
+                    election_id,
+                    tally,
+                    winners,
+                    tied_candidates,
+                    U256::from(1u32),
+                    total_votes,
+                    rounds,
+                );
+            }
+            
+            // Eliminate the last-placed candidate
+            eliminated[last] = true;
+            remaining = remaining - 1;
+            continuing = continuing - self.transfer_ballots(election_id, last, &eliminated, &mut counts, &mut transferred);
+        }
+    }
+    
+    // Move an eliminated candidate's ballots (first preferences, then ballots transferred
+    // to them earlier) to each ballot's next continuing choice; returns the exhausted weight
+    fn transfer_ballots(
+        &self,
+        election_id: U256,
+        from: usize,
+        eliminated: &[bool; MAX_RANKED_CANDIDATES as usize],
+        counts: &mut [U256; MAX_RANKED_CANDIDATES as usize],
+        transferred: &mut Vec<Vec<(U256, usize)>>,
+    ) -> U256 {
+        counts[from] = U256::ZERO;
+        
+        let mut ballots: Vec<(U256, usize)> = Vec::new();
+        let bucket_size = self.first_preference_counts.get(election_id).get(U256::from(from));
+        let mut i = U256::ZERO;
+        while i < bucket_size {
+            ballots.push((self.first_preference_ballots.get(election_id).get(U256::from(from)).get(i), 0));
+            i = i + U256::from(1u32);
+        }
+        ballots.append(&mut transferred[from]);
+        
+        let mut exhausted = U256::ZERO;
+        for (ballot_id, rank) in ballots {
+            let length = self.ranked_ballot_lengths.get(election_id).get(ballot_id).as_usize();
+            let weight = self.ranked_ballot_weights.get(election_id).get(ballot_id);
+            let mut next = None;
+            for next_rank in (rank + 1)..length {
+                let candidate = self.ranked_ballots.get(election_id).get(ballot_id).get(U256::from(next_rank)).as_usize();
+                if !eliminated[candidate] {
+                    next = Some((candidate, next_rank));
+                    break;
+                }
+            }
+            match next {
+                Some((candidate, next_rank)) => {
+                    counts[candidate] = counts[candidate] + weight;
+                    transferred[candidate].push((ballot_id, next_rank));
+                }
+                None => exhausted = exhausted + weight,
+            }
+        }
+        exhausted
+    }
+    
+    // Order the tally by votes, most first; equal counts are ordered by candidate ID
+    fn sort_tally(tally: &mut Vec<CandidateTally>) {
+        tally.sort_unstable_by(|a, b| b.votes.cmp(&a.votes).then(a.candidate_id.cmp(&b.candidate_id)));
+    }
+    
+    // Split a sorted tally into candidates elected outright and candidates tied for the last seats
+    fn seat_cutoff(tally: &[CandidateTally], seats: usize) -> (Vec<U256>, Vec<U256>) {
+        // Fewer candidates than seats: everyone is elected
+        if tally.len() <= seats {
+            return (tally.iter().map(|t| t.candidate_id).collect(), Vec::new());
+        }
+        
+        // No tie unless the first unelected candidate matches the last seat's count
+        let cutoff_votes = tally[seats - 1].votes;
+        if tally[seats].votes != cutoff_votes {
+            return (tally[..seats].iter().map(|t| t.candidate_id).collect(), Vec::new());
+        }
+        
+        let winners = tally.iter().filter(|t| t.votes > cutoff_votes).map(|t| t.candidate_id).collect();
+        let tied_candidates = tally.iter().filter(|t| t.votes == cutoff_votes).map(|t| t.candidate_id).collect();
+        (winners, tied_candidates)
+    }
+    
+    // Assemble the result from a sorted tally; ties leave the lowest tied ID in first place
+    fn build_result(
+        election_id: U256,
+        tally: Vec<CandidateTally>,
+        winners: Vec<U256>,
+        tied_candidates: Vec<U256>,
+        seats: U256,
+        total_votes: U256,
+        rounds: U256,
+    ) -> ElectionResult {
+        let winning_candidate = tally[0].candidate_id;
+        let winning_votes = tally[0].votes;
+        
+        ElectionResult {
+            election_id,
+            has_winner: tied_candidates.is_empty(),
+            winning_candidate,
+            winning_votes,
+            total_votes,
+            rounds,
+            seats,
+            winners,
+            tied_candidates,
+            tally,
+            unrevealed_commits: U256::ZERO,
+            outcome: OUTCOME_NONE,
+            participants: U256::ZERO,
+            electorate: U256::ZERO,
+            turnout_basis_points: U256::ZERO,
+        }
+    }
+}
+
+// Helper function to get current block timestamp
+fn block_timestamp() -> U256 {
+    U256::from(stylus_sdk::block::timestamp())
+}
+
+// Helper function to get current block number
+fn block_number() -> U256 {
+    U256::from(stylus_sdk::block::number())
 } 
\ No newline at end of file